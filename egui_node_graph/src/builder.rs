@@ -0,0 +1,365 @@
+use super::*;
+use egui::{pos2, Pos2, Vec2};
+use std::collections::HashMap;
+
+/// Space between the nodes placed by [`GraphEditorState::build_node`] and
+/// [`GraphEditorState::arrange_nodes`].
+const NODE_SPACING: Vec2 = Vec2::new(80.0, 40.0);
+
+/// Errors that can happen when connecting ports with [`Graph::connect`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum BuildError {
+    #[error("Node {node:?} has no input named {name:?}")]
+    UnknownInput { node: NodeId, name: String },
+
+    #[error("Node {node:?} has no output named {name:?}")]
+    UnknownOutput { node: NodeId, name: String },
+
+    #[error("Parameter {0:?} was not found in the graph.")]
+    InvalidParameterId(AnyParameterId),
+
+    #[error("The output {output:?} and the input {input:?} have different data types")]
+    TypeMismatch { output: OutputId, input: InputId },
+
+    #[error("The input {0:?} is already connected")]
+    InputAlreadyConnected(InputId),
+}
+
+/// An output to connect with [`Graph::connect`], usually obtained with
+/// [`NodeHandle::out`]. Looking up an unknown name doesn't fail right away:
+/// The error is returned when connecting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputRef(Result<OutputId, BuildError>);
+
+impl OutputRef {
+    pub fn id(&self) -> Result<OutputId, BuildError> {
+        self.0.clone()
+    }
+}
+
+impl From<OutputId> for OutputRef {
+    fn from(output: OutputId) -> Self {
+        Self(Ok(output))
+    }
+}
+
+/// An input to connect with [`Graph::connect`], usually obtained with
+/// [`NodeHandle::input`]. See [`OutputRef`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputRef(Result<InputId, BuildError>);
+
+impl InputRef {
+    pub fn id(&self) -> Result<InputId, BuildError> {
+        self.0.clone()
+    }
+}
+
+impl From<InputId> for InputRef {
+    fn from(input: InputId) -> Self {
+        Self(Ok(input))
+    }
+}
+
+/// The ports of a node, as returned by [`NodeBuilder::finish`]. Unlike a
+/// [`Node`], it doesn't borrow the graph, so several handles can be used
+/// while connecting their ports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeHandle {
+    pub id: NodeId,
+    pub inputs: Vec<(String, InputId)>,
+    pub outputs: Vec<(String, OutputId)>,
+}
+
+impl NodeHandle {
+    /// The input named `name`.
+    pub fn input(&self, name: &str) -> InputRef {
+        InputRef(
+            (self.inputs.iter())
+                .find(|(param_name, _)| param_name == name)
+                .map(|(_, id)| *id)
+                .ok_or_else(|| BuildError::UnknownInput {
+                    node: self.id,
+                    name: name.to_owned(),
+                }),
+        )
+    }
+
+    /// The output named `name`.
+    pub fn out(&self, name: &str) -> OutputRef {
+        OutputRef(
+            (self.outputs.iter())
+                .find(|(param_name, _)| param_name == name)
+                .map(|(_, id)| *id)
+                .ok_or_else(|| BuildError::UnknownOutput {
+                    node: self.id,
+                    name: name.to_owned(),
+                }),
+        )
+    }
+}
+
+/// Adds parameters to a node created with [`Graph::build_node`] or
+/// [`GraphEditorState::build_node`].
+pub struct NodeBuilder<'a, NodeData, DataType, ValueType> {
+    graph: &'a mut Graph<NodeData, DataType, ValueType>,
+    node_id: NodeId,
+}
+
+impl<'a, NodeData, DataType, ValueType> NodeBuilder<'a, NodeData, DataType, ValueType> {
+    /// Adds an input that can be connected or edited inline.
+    pub fn input(self, name: impl Into<String>, typ: DataType, value: ValueType) -> Self {
+        self.input_with_kind(name, typ, value, InputParamKind::ConnectionOrConstant)
+    }
+
+    /// Adds an input of the given kind.
+    pub fn input_with_kind(
+        self,
+        name: impl Into<String>,
+        typ: DataType,
+        value: ValueType,
+        kind: InputParamKind,
+    ) -> Self {
+        (self.graph).add_input_param(self.node_id, name.into(), typ, value, kind, true);
+        self
+    }
+
+    pub fn output(self, name: impl Into<String>, typ: DataType) -> Self {
+        (self.graph).add_output_param(self.node_id, name.into(), typ);
+        self
+    }
+
+    /// Returns the handle used to connect the ports of the node.
+    pub fn finish(self) -> NodeHandle {
+        self.graph.node_handle(self.node_id)
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Adds a node without parameters, returning a builder to add them, e.g.
+    /// `graph.build_node("Add", data).input("A", typ, value).output("out", typ).finish()`.
+    pub fn build_node(
+        &mut self,
+        label: impl Into<String>,
+        user_data: NodeData,
+    ) -> NodeBuilder<'_, NodeData, DataType, ValueType> {
+        let node_id = self.add_node(label.into(), user_data, |_, _| {});
+        NodeBuilder {
+            graph: self,
+            node_id,
+        }
+    }
+
+    /// The handle of an existing node, to connect its ports by name.
+    pub fn node_handle(&self, node_id: NodeId) -> NodeHandle {
+        let node = &self[node_id];
+        NodeHandle {
+            id: node_id,
+            inputs: node.inputs.clone(),
+            outputs: node.outputs.clone(),
+        }
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Connects `output` to `input`, e.g.
+    /// `graph.connect(a.out("out"), b.input("A"))?`. Fails if a port is
+    /// unknown, if their data types differ, or if the input is already
+    /// connected.
+    pub fn connect(
+        &mut self,
+        output: impl Into<OutputRef>,
+        input: impl Into<InputRef>,
+    ) -> Result<(), BuildError> {
+        let (output, input) = (output.into().0?, input.into().0?);
+        let output_param =
+            (self.try_get_output(output)).ok_or(BuildError::InvalidParameterId(output.into()))?;
+        let input_param =
+            (self.try_get_input(input)).ok_or(BuildError::InvalidParameterId(input.into()))?;
+        if output_param.typ != input_param.typ {
+            return Err(BuildError::TypeMismatch { output, input });
+        }
+        if self.connection(input).is_some() {
+            return Err(BuildError::InputAlreadyConnected(input));
+        }
+        self.add_connection(output, input);
+        Ok(())
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Like [`Graph::build_node`], but also adds the node to the editor,
+    /// placing it to the right of the last added node. Call
+    /// [`Self::arrange_nodes`] once the graph is built to lay it out.
+    pub fn build_node(
+        &mut self,
+        label: impl Into<String>,
+        user_data: NodeData,
+    ) -> NodeBuilder<'_, NodeData, DataType, ValueType> {
+        // Only the last node is estimated, so building a graph stays linear.
+        let right = match self.node_order.last() {
+            Some(last) if self.graph.nodes.contains_key(*last) => {
+                let mut layout = GraphLayout::default();
+                layout.estimate_node(self, *last);
+                layout.node_rects[last].right() + NODE_SPACING.x
+            }
+            _ => 0.0,
+        };
+        let node_id = self.graph.add_node(label.into(), user_data, |_, _| {});
+        self.node_positions.insert(node_id, pos2(right, 0.0));
+        self.node_order.push(node_id);
+        NodeBuilder {
+            graph: &mut self.graph,
+            node_id,
+        }
+    }
+
+    /// Places the nodes in columns, from left to right, so that every
+    /// connection goes to a column further right when the graph has no
+    /// cycles. Nodes of a column are stacked in drawing order.
+    pub fn arrange_nodes(&mut self) {
+        let nodes: Vec<NodeId> = (self.node_order.iter().copied())
+            .filter(|node_id| self.graph.nodes.contains_key(*node_id))
+            .collect();
+        let mut columns: HashMap<NodeId, usize> =
+            nodes.iter().map(|node_id| (*node_id, 0)).collect();
+        // Longest path from the nodes without inputs. Bounded by the number
+        // of nodes, so cycles don't push nodes to the right forever.
+        for _ in 0..nodes.len() {
+            let mut changed = false;
+            for (input, output) in self.graph.iter_connections() {
+                let (from, to) = match (
+                    self.graph.try_get_output(output),
+                    self.graph.try_get_input(input),
+                ) {
+                    (Some(output), Some(input)) => (output.node, input.node),
+                    _ => continue,
+                };
+                let column = columns.get(&from).map(|column| column + 1).unwrap_or(0);
+                match columns.get_mut(&to) {
+                    Some(to_column) if *to_column < column => {
+                        *to_column = column;
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let layout = GraphLayout::estimate(self);
+        let column_count = columns.values().map(|column| column + 1).max().unwrap_or(0);
+        let mut widths = vec![0.0f32; column_count];
+        for node_id in &nodes {
+            let width = &mut widths[columns[node_id]];
+            *width = width.max(layout.node_rects[node_id].width());
+        }
+        let mut x = 0.0;
+        let column_xs: Vec<f32> = (widths.iter())
+            .map(|width| {
+                let column_x = x;
+                x += width + NODE_SPACING.x;
+                column_x
+            })
+            .collect();
+
+        let mut ys = vec![0.0f32; column_count];
+        for node_id in &nodes {
+            let column = columns[node_id];
+            let position = Pos2::new(column_xs[column], ys[column]);
+            ys[column] += layout.node_rects[node_id].height() + NODE_SPACING.y;
+            self.node_positions.insert(*node_id, position);
+        }
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Connects two ports of the graph, see [`Graph::connect`].
+    pub fn connect(
+        &mut self,
+        output: impl Into<OutputRef>,
+        input: impl Into<InputRef>,
+    ) -> Result<(), BuildError> {
+        self.graph.connect(output, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), &'static str, f32, (), ()>;
+
+    fn add(state: &mut TestState) -> NodeHandle {
+        (state.build_node("Add", ()))
+            .input("A", "scalar", 0.0)
+            .input("B", "scalar", 0.0)
+            .output("out", "scalar")
+            .finish()
+    }
+
+    #[test]
+    fn connects_ports_by_name() {
+        let mut state = TestState::new(1.0);
+        let a = add(&mut state);
+        let b = add(&mut state);
+        let v = (state.build_node("Vector", ()))
+            .output("out", "vector")
+            .finish();
+
+        state.connect(a.out("out"), b.input("B")).unwrap();
+        assert_eq!(state.graph.connection(b.inputs[1].1), Some(a.outputs[0].1));
+
+        assert_eq!(
+            state.connect(a.out("out"), b.input("C")),
+            Err(BuildError::UnknownInput {
+                node: b.id,
+                name: "C".into()
+            })
+        );
+        assert!(matches!(
+            state.connect(v.out("out"), b.input("A")),
+            Err(BuildError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            state.connect(b.out("out"), b.input("B")),
+            Err(BuildError::InputAlreadyConnected(_))
+        ));
+    }
+
+    #[test]
+    fn places_new_nodes_after_the_last_one() {
+        let mut state = TestState::new(1.0);
+        let a = add(&mut state);
+        state.node_sizes.insert(a.id, egui::vec2(500.0, 100.0));
+        let b = add(&mut state);
+        let c = add(&mut state);
+
+        let x = |node: &NodeHandle| state.node_positions[node.id].x;
+        assert_eq!(x(&a), 0.0);
+        assert_eq!(x(&b), 500.0 + NODE_SPACING.x);
+        assert!(x(&c) > x(&b));
+    }
+
+    #[test]
+    fn arranges_nodes_in_columns() {
+        let mut state = TestState::new(1.0);
+        let c = add(&mut state);
+        let a = add(&mut state);
+        let b = add(&mut state);
+        state.connect(a.out("out"), b.input("A")).unwrap();
+        state.connect(b.out("out"), c.input("A")).unwrap();
+        state.connect(a.out("out"), c.input("B")).unwrap();
+        state.arrange_nodes();
+
+        let x = |node: &NodeHandle| state.node_positions[node.id].x;
+        assert_eq!(x(&a), 0.0);
+        assert!(x(&a) < x(&b) && x(&b) < x(&c));
+        assert_eq!(state.node_positions[a.id].y, 0.0);
+    }
+}
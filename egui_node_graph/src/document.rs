@@ -0,0 +1,394 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Errors that can happen when importing a [`GraphDocument`].
+#[derive(Debug, thiserror::Error)]
+pub enum DocumentError {
+    #[error("More than one node has the id {0:?}")]
+    DuplicateNodeId(String),
+
+    #[error("The node id {0:?} contains a \".\", which separates node ids from parameter names")]
+    InvalidNodeId(String),
+
+    #[error("The connection endpoint {0:?} is not of the form \"node.param\"")]
+    InvalidEndpoint(String),
+
+    #[error("There is no node with id {0:?}")]
+    UnknownNode(String),
+
+    #[error("The node {node:?} has no {kind} named {param:?}")]
+    UnknownParameter {
+        node: String,
+        param: String,
+        kind: &'static str,
+    },
+
+    #[error("The input {0:?} has more than one incoming connection")]
+    InputAlreadyConnected(String),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// An input parameter in a [`DocumentNode`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DocumentInput<DataType, ValueType> {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: DataType,
+    pub value: ValueType,
+    pub kind: InputParamKind,
+    #[serde(default = "default_shown_inline")]
+    pub shown_inline: bool,
+}
+
+fn default_shown_inline() -> bool {
+    true
+}
+
+/// An output parameter in a [`DocumentNode`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DocumentOutput<DataType> {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: DataType,
+}
+
+/// A node in a [`GraphDocument`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DocumentNode<NodeData, DataType, ValueType> {
+    /// The stable identifier of the node. Must be unique in the document and
+    /// not contain dots.
+    pub id: String,
+    pub label: String,
+    /// The position of the node in the editor, as `[x, y]`.
+    #[serde(default)]
+    pub position: [f32; 2],
+    pub inputs: Vec<DocumentInput<DataType, ValueType>>,
+    pub outputs: Vec<DocumentOutput<DataType>>,
+    pub data: NodeData,
+}
+
+/// A connection in a [`GraphDocument`]. Both ends are written as
+/// `"node_id.param_name"`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DocumentConnection {
+    /// The output the connection starts at.
+    pub from: String,
+    /// The input the connection ends at.
+    pub to: String,
+}
+
+/// A graph in a format meant to be read, reviewed and edited by humans.
+///
+/// Unlike the serialized [`GraphEditorState`], it doesn't contain any slotmap
+/// keys: Nodes are identified by the stable ids of
+/// [`GraphEditorState::stable_ids`], and connections refer to parameters by
+/// name. Nodes are listed in drawing order and connections are sorted, so
+/// saving an unchanged graph produces the same file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphDocument<NodeData, DataType, ValueType> {
+    pub nodes: Vec<DocumentNode<NodeData, DataType, ValueType>>,
+    #[serde(default)]
+    pub connections: Vec<DocumentConnection>,
+}
+
+impl<NodeData, DataType, ValueType> GraphDocument<NodeData, DataType, ValueType>
+where
+    NodeData: Serialize + DeserializeOwned,
+    DataType: Serialize + DeserializeOwned,
+    ValueType: Serialize + DeserializeOwned,
+{
+    /// Serializes the document as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parses a document from JSON.
+    pub fn from_json(json: &str) -> Result<Self, DocumentError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Turns a node label into an identifier made of lowercase letters, digits
+/// and underscores.
+fn slugify(label: &str) -> String {
+    let slug = label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    let slug = slug.trim_matches('_');
+    if slug.is_empty() {
+        "node".to_owned()
+    } else {
+        slug.to_owned()
+    }
+}
+
+fn split_endpoint(endpoint: &str) -> Result<(&str, &str), DocumentError> {
+    endpoint
+        .split_once('.')
+        .ok_or_else(|| DocumentError::InvalidEndpoint(endpoint.to_owned()))
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Gives a stable id to every node that doesn't have one yet, derived
+    /// from its label, e.g. `add_2`.
+    pub fn assign_stable_ids(&mut self) {
+        let mut taken: HashSet<String> = self.stable_ids.values().cloned().collect();
+        let node_ids: Vec<_> = self.node_order.clone();
+        for node_id in node_ids {
+            if self.stable_ids.contains_key(node_id) || !self.graph.nodes.contains_key(node_id) {
+                continue;
+            }
+            let slug = slugify(&self.graph[node_id].label);
+            let id = (1..)
+                .map(|n| format!("{slug}_{n}"))
+                .find(|id| !taken.contains(id))
+                .expect("There are infinitely many ids");
+            taken.insert(id.clone());
+            self.stable_ids.insert(node_id, id);
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Exports the graph and the node positions as a [`GraphDocument`].
+    /// Nodes without a stable id get one, see [`Self::assign_stable_ids`].
+    pub fn to_document(&mut self) -> GraphDocument<NodeData, DataType, ValueType> {
+        self.assign_stable_ids();
+        let graph = &self.graph;
+
+        let nodes = self
+            .node_order
+            .iter()
+            .filter(|node_id| graph.nodes.contains_key(**node_id))
+            .map(|node_id| {
+                let node = &graph[*node_id];
+                let position = self
+                    .node_positions
+                    .get(*node_id)
+                    .copied()
+                    .unwrap_or(egui::Pos2::ZERO);
+                DocumentNode {
+                    id: self.stable_ids[*node_id].clone(),
+                    label: node.label.clone(),
+                    position: [position.x, position.y],
+                    inputs: node
+                        .inputs
+                        .iter()
+                        .map(|(name, input)| {
+                            let param = &graph[*input];
+                            DocumentInput {
+                                name: name.clone(),
+                                typ: param.typ.clone(),
+                                value: param.value.clone(),
+                                kind: param.kind,
+                                shown_inline: param.shown_inline,
+                            }
+                        })
+                        .collect(),
+                    outputs: node
+                        .outputs
+                        .iter()
+                        .map(|(name, output)| DocumentOutput {
+                            name: name.clone(),
+                            typ: graph[*output].typ.clone(),
+                        })
+                        .collect(),
+                    data: node.user_data.clone(),
+                }
+            })
+            .collect();
+
+        let endpoint = |node_id: NodeId, param: AnyParameterId| {
+            let name = graph.param_name(param)?;
+            Some(format!("{}.{}", self.stable_ids.get(node_id)?, name))
+        };
+        let mut connections: Vec<_> = graph
+            .iter_connections()
+            .filter_map(|(input, output)| {
+                Some(DocumentConnection {
+                    from: endpoint(graph.try_get_output(output)?.node, output.into())?,
+                    to: endpoint(graph.try_get_input(input)?.node, input.into())?,
+                })
+            })
+            .collect();
+        connections.sort();
+
+        GraphDocument { nodes, connections }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Builds an editor state from a [`GraphDocument`]. The stable ids of the
+    /// document are kept, so exporting the state again produces the same
+    /// document.
+    pub fn from_document(
+        document: GraphDocument<NodeData, DataType, ValueType>,
+    ) -> Result<Self, DocumentError> {
+        let mut state = Self::new(1.0);
+        let mut nodes_by_id = HashMap::new();
+
+        for doc_node in document.nodes {
+            if doc_node.id.contains('.') {
+                return Err(DocumentError::InvalidNodeId(doc_node.id));
+            }
+            if nodes_by_id.contains_key(&doc_node.id) {
+                return Err(DocumentError::DuplicateNodeId(doc_node.id));
+            }
+            let node_id = state
+                .graph
+                .add_node(doc_node.label, doc_node.data, |graph, node_id| {
+                    for input in doc_node.inputs {
+                        graph.add_input_param(
+                            node_id,
+                            input.name,
+                            input.typ,
+                            input.value,
+                            input.kind,
+                            input.shown_inline,
+                        );
+                    }
+                    for output in doc_node.outputs {
+                        graph.add_output_param(node_id, output.name, output.typ);
+                    }
+                });
+            let [x, y] = doc_node.position;
+            state.node_positions.insert(node_id, egui::pos2(x, y));
+            state.node_order.push(node_id);
+            state.stable_ids.insert(node_id, doc_node.id.clone());
+            nodes_by_id.insert(doc_node.id, node_id);
+        }
+
+        let find_node = |id: &str| {
+            nodes_by_id
+                .get(id)
+                .copied()
+                .ok_or_else(|| DocumentError::UnknownNode(id.to_owned()))
+        };
+        let unknown_param = |node: &str, param: &str, kind| DocumentError::UnknownParameter {
+            node: node.to_owned(),
+            param: param.to_owned(),
+            kind,
+        };
+        for connection in &document.connections {
+            let (from_node, from_param) = split_endpoint(&connection.from)?;
+            let (to_node, to_param) = split_endpoint(&connection.to)?;
+            let output = state.graph[find_node(from_node)?]
+                .get_output(from_param)
+                .map_err(|_| unknown_param(from_node, from_param, "output"))?;
+            let input = state.graph[find_node(to_node)?]
+                .get_input(to_param)
+                .map_err(|_| unknown_param(to_node, to_param, "input"))?;
+            if state.graph.connection(input).is_some() {
+                return Err(DocumentError::InputAlreadyConnected(connection.to.clone()));
+            }
+            state.graph.add_connection(output, input);
+        }
+
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestState = GraphEditorState<(), String, f32, (), ()>;
+
+    #[test]
+    fn document_round_trip() {
+        let mut state = TestState::new(1.0);
+        let add_node = |state: &mut TestState, label: &str, pos: egui::Pos2| {
+            let node_id = state.graph.add_node(label.into(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "A".into(),
+                    "scalar".into(),
+                    1.0,
+                    InputParamKind::ConnectionOrConstant,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), "scalar".into());
+            });
+            state.node_positions.insert(node_id, pos);
+            state.node_order.push(node_id);
+            node_id
+        };
+        let a = add_node(&mut state, "Add", egui::pos2(10.0, 20.0));
+        let b = add_node(&mut state, "Add", egui::pos2(200.0, 20.0));
+        let output = state.graph[a].get_output("out").unwrap();
+        let input = state.graph[b].get_input("A").unwrap();
+        state.graph.add_connection(output, input);
+
+        let json = state.to_document().to_json().unwrap();
+        assert!(json.contains("\"from\": \"add_1.out\""));
+        assert!(json.contains("\"to\": \"add_2.A\""));
+
+        let mut loaded =
+            TestState::from_document(GraphDocument::from_json(&json).unwrap()).unwrap();
+        assert_eq!(loaded.graph.nodes.len(), 2);
+        assert_eq!(loaded.graph.iter_connections().count(), 1);
+        assert_eq!(loaded.to_document().to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn unknown_parameters_are_reported() {
+        let mut state = TestState::new(1.0);
+        let node_id = state.graph.add_node("Add".into(), (), |_, _| {});
+        state.node_order.push(node_id);
+        let mut document = state.to_document();
+        document.connections.push(DocumentConnection {
+            from: "add_1.out".into(),
+            to: "add_1.A".into(),
+        });
+
+        assert!(matches!(
+            TestState::from_document(document),
+            Err(DocumentError::UnknownParameter { kind: "output", .. })
+        ));
+    }
+
+    #[test]
+    fn invalid_node_ids_are_rejected() {
+        let mut state = TestState::new(1.0);
+        for _ in 0..2 {
+            let node_id = state.graph.add_node("Add".into(), (), |_, _| {});
+            state.node_order.push(node_id);
+        }
+        let document = state.to_document();
+
+        let mut duplicated = document.clone();
+        duplicated.nodes[1].id = "add_1".into();
+        assert!(matches!(
+            TestState::from_document(duplicated),
+            Err(DocumentError::DuplicateNodeId(id)) if id == "add_1"
+        ));
+
+        let mut dotted = document;
+        dotted.nodes[0].id = "add.1".into();
+        assert!(matches!(
+            TestState::from_document(dotted),
+            Err(DocumentError::InvalidNodeId(id)) if id == "add.1"
+        ));
+    }
+}
@@ -1,7 +1,6 @@
 use std::collections::HashSet;
 
-use crate::color_hex_utils::*;
-use crate::utils::ColorUtils;
+use crate::graph_impls::reachable_nodes;
 
 use super::*;
 use egui::epaint::{CubicBezierShape, RectShape};
@@ -11,6 +10,9 @@ pub type PortLocations = std::collections::HashMap<AnyParameterId, Pos2>;
 pub type NodeRects = std::collections::HashMap<NodeId, Rect>;
 
 const DISTANCE_TO_CONNECT: f32 = 10.0;
+const DISTANCE_TO_HOVER_CONNECTION: f32 = 6.0;
+/// Nodes further than this from the editor rect are not drawn.
+const CULLING_MARGIN: f32 = 20.0;
 
 /// Nodes communicate certain events to the parent graph when drawn. There is
 /// one special `User` variant which can be used by users as the return value
@@ -24,6 +26,12 @@ pub enum NodeResponse<UserResponse: UserResponseTrait, NodeData: NodeDataTrait>
     },
     CreatedNode(NodeId),
     SelectNode(NodeId),
+    /// Emitted when a connection is clicked, either to select it or to open
+    /// its context menu.
+    SelectConnection {
+        input: InputId,
+        output: OutputId,
+    },
     /// As a user of this library, prefer listening for `DeleteNodeFull` which
     /// will also contain the user data for the deleted node.
     DeleteNodeUi(NodeId),
@@ -40,10 +48,22 @@ pub enum NodeResponse<UserResponse: UserResponseTrait, NodeData: NodeDataTrait>
     },
     /// Emitted when a node is interacted with, and should be raised
     RaiseNode(NodeId),
+    /// Collapses or expands a node. Collapsed nodes only show their title
+    /// bar and connected ports.
+    CollapseNode {
+        node: NodeId,
+        collapsed: bool,
+    },
     MoveNode {
         node: NodeId,
         drag_delta: Vec2,
     },
+    /// Sets the size of a node, including its margins. Emitted when the user
+    /// drags the resize handle of a node.
+    ResizeNode {
+        node: NodeId,
+        size: Vec2,
+    },
     User(UserResponse),
 }
 
@@ -72,15 +92,73 @@ impl<UserResponse: UserResponseTrait, NodeData: NodeDataTrait> Default
         }
     }
 }
+/// The rects of the nodes and the locations of the ports, in graph
+/// coordinates: The coordinates of [`GraphEditorState::node_positions`],
+/// before panning.
+#[derive(Clone, Debug, Default)]
+pub struct GraphLayout {
+    pub node_rects: NodeRects,
+    /// The height of the title bar of each node.
+    pub titlebar_heights: std::collections::HashMap<NodeId, f32>,
+    pub port_locations: PortLocations,
+}
+
+/// The height of the `index`-th of `count` ports on one side of a collapsed
+/// node, spreading them evenly along the edge of `titlebar_rect` so their
+/// wires don't end at the same point.
+pub(crate) fn collapsed_port_y(titlebar_rect: Rect, index: usize, count: usize) -> f32 {
+    titlebar_rect.top() + titlebar_rect.height() * (index + 1) as f32 / (count + 1) as f32
+}
+
+/// The ports shown by a collapsed node and their positions. Only the connected
+/// ports are shown, spread along the sides of `titlebar_rect` with
+/// [`collapsed_port_y`].
+pub(crate) fn collapsed_ports<NodeData, DataType, ValueType>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    node_id: NodeId,
+    titlebar_rect: Rect,
+) -> Vec<(AnyParameterId, Pos2)> {
+    let node = &graph[node_id];
+    let connected_inputs: Vec<_> = node
+        .input_ids()
+        .filter(|input| graph.connection(*input).is_some())
+        .map(AnyParameterId::Input)
+        .collect();
+    let connected_outputs: Vec<_> = node
+        .output_ids()
+        .filter(|output| graph.connections.values().any(|o| o == output))
+        .map(AnyParameterId::Output)
+        .collect();
+    let spread = |params: Vec<AnyParameterId>, port_x: f32| {
+        let count = params.len();
+        params
+            .into_iter()
+            .enumerate()
+            .map(move |(index, param_id)| {
+                let port_y = collapsed_port_y(titlebar_rect, index, count);
+                (param_id, pos2(port_x, port_y))
+            })
+    };
+    spread(connected_inputs, titlebar_rect.left())
+        .chain(spread(connected_outputs, titlebar_rect.right()))
+        .collect()
+}
+
 pub struct GraphNodeWidget<'a, NodeData, DataType, ValueType> {
     pub position: &'a mut Pos2,
+    /// The size chosen by the user, if the node has been resized.
+    pub size: Option<Vec2>,
     pub graph: &'a mut Graph<NodeData, DataType, ValueType>,
     pub port_locations: &'a mut PortLocations,
     pub node_rects: &'a mut NodeRects,
     pub node_id: NodeId,
     pub ongoing_drag: Option<(NodeId, AnyParameterId)>,
     pub selected: bool,
+    pub collapsed: bool,
+    /// Locked nodes can't be edited, see [`GraphEditorState::locked_nodes`].
+    pub locked: bool,
     pub pan: egui::Vec2,
+    pub style: &'a GraphStyle,
 }
 
 impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
@@ -119,6 +197,11 @@ where
         let editor_rect = ui.max_rect();
         let resp = ui.allocate_rect(editor_rect, Sense::hover());
 
+        let style = self
+            .style
+            .clone()
+            .unwrap_or_else(|| GraphStyle::from_visuals(ui.visuals()));
+
         let cursor_pos = ui
             .ctx()
             .input(|i| i.pointer.hover_pos().unwrap_or(Pos2::ZERO));
@@ -158,25 +241,57 @@ where
             drag_released_on_background = true;
         }
 
+        // Shapes drawn over each node to dim it, filled in once the
+        // highlighted dependencies are known.
+        let mut node_overlays = Vec::new();
+
         /* Draw nodes */
+        let pan = self.pan_zoom.pan + editor_rect.min.to_vec2();
+        let culling_rect = editor_rect.expand(CULLING_MARGIN);
         for node_id in self.node_order.iter().copied() {
+            // Nodes that would be outside the editor are skipped. Their
+            // metrics are estimated from the last time they were drawn.
+            if self.ongoing_node_drag != Some(node_id) {
+                let node_pos = self.node_positions[node_id] + pan;
+                if let Some(layout) = NodeLayoutMemory::load(ui.ctx(), ui.id(), node_id) {
+                    if !culling_rect.intersects(layout.rect.translate(node_pos.to_vec2())) {
+                        layout.estimate_metrics(
+                            &self.graph[node_id],
+                            node_pos,
+                            &mut port_locations,
+                            &mut node_rects,
+                        );
+                        continue;
+                    }
+                }
+            }
+
             let responses = GraphNodeWidget {
                 position: self.node_positions.get_mut(node_id).unwrap(),
+                size: self.node_sizes.get(node_id).copied(),
                 graph: &mut self.graph,
                 port_locations: &mut port_locations,
                 node_rects: &mut node_rects,
                 node_id,
                 ongoing_drag: self.connection_in_progress,
-                selected: self
-                    .selected_nodes
-                    .iter()
-                    .any(|selected| *selected == node_id),
-                pan: self.pan_zoom.pan + editor_rect.min.to_vec2(),
+                selected: self.selected_nodes.contains(&node_id),
+                collapsed: self.collapsed_nodes.contains(&node_id),
+                locked: self.read_only || self.locked_nodes.contains(&node_id),
+                pan,
+                style: &style,
             }
             .show(ui, user_state);
+            node_overlays.push((node_id, ui.painter().add(Shape::Noop)));
 
-            // Actions executed later
-            delayed_responses.extend(responses);
+            // Actions executed later. The node only knows whether it is locked
+            // itself, so dragging a connection off its input is dropped here
+            // when the node at the other end is locked.
+            delayed_responses.extend(responses.into_iter().filter(|response| match response {
+                NodeResponse::DisconnectEvent { input, output } => {
+                    !self.is_connection_locked(*input, *output)
+                }
+                _ => true,
+            }));
         }
 
         /* Draw the node finder, if open */
@@ -187,7 +302,7 @@ where
                 node_finder_area = node_finder_area.current_pos(pos);
             }
             node_finder_area.show(ui.ctx(), |ui| {
-                if let Some(node_kind) = node_finder.show(ui, all_kinds, user_state) {
+                if let Some(node_kind) = node_finder.show(ui, all_kinds, user_state, &style) {
                     let new_node = self.graph.add_node(
                         node_kind.node_graph_label(user_state),
                         node_kind.user_data(user_state),
@@ -215,10 +330,152 @@ where
             self.node_finder = None;
         }
 
+        // Connections removed by interacting with them. The corresponding
+        // disconnect responses are reported after processing all others.
+        let mut removed_connections: Vec<(InputId, OutputId)> = Vec::new();
+
+        /* Connection hovering and clicking */
+        self.selected_connections
+            .retain(|(input, output)| self.graph.connection(*input) == Some(*output));
+        self.connection_annotations
+            .retain(|(input, output), _| self.graph.connection(*input) == Some(*output));
+
+        let cursor_over_node = node_rects.values().any(|rect| rect.contains(cursor_pos));
+        let hovered_connection = if cursor_in_editor
+            && !cursor_in_finder
+            && !cursor_over_node
+            && self.connection_in_progress.is_none()
+            && self.ongoing_node_drag.is_none()
+            && self.ongoing_knife_cut.is_none()
+        {
+            self.graph
+                .iter_connections()
+                .map(|(input, output)| {
+                    let src_pos = port_locations[&AnyParameterId::Output(output)];
+                    let dst_pos = port_locations[&AnyParameterId::Input(input)];
+                    let routing = wire_routing(&self.graph, output, user_state, &style);
+                    let distance = distance_to_polyline(
+                        cursor_pos,
+                        &connection_polyline(src_pos, dst_pos, routing, &style),
+                    );
+                    ((input, output), distance)
+                })
+                .filter(|(_, distance)| *distance < DISTANCE_TO_HOVER_CONNECTION)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(connection, _)| connection)
+        } else {
+            None
+        };
+
+        let mut click_on_connection = false;
+        if let Some((input, output)) = hovered_connection {
+            if click_on_background {
+                click_on_connection = true;
+                delayed_responses.push(NodeResponse::SelectConnection { input, output });
+            } else if r.secondary_clicked() && !ui.ctx().input(|i| i.modifiers.command) {
+                click_on_connection = true;
+                delayed_responses.push(NodeResponse::SelectConnection { input, output });
+                self.node_finder = None;
+                self.context_menu = Some(ContextMenu {
+                    target: ContextMenuTarget::Connection { input, output },
+                    position: cursor_pos,
+                });
+            }
+        }
+
+        /* Draw the context menu, if open */
+        if let Some(context_menu) = self.context_menu {
+            let mut should_close_context_menu = false;
+            let menu_response = Area::new("context_menu")
+                .order(Order::Foreground)
+                .fixed_pos(context_menu.position)
+                .show(ui.ctx(), |ui| {
+                    Frame::menu(ui.style()).show(ui, |ui| {
+                        should_close_context_menu = self.context_menu_ui(
+                            ui,
+                            context_menu.target,
+                            user_state,
+                            &mut delayed_responses,
+                            &mut removed_connections,
+                        );
+                    });
+                })
+                .response;
+
+            let clicked_elsewhere = ui.ctx().input(|i| i.pointer.any_pressed())
+                && !menu_response.rect.contains(cursor_pos);
+            if should_close_context_menu || clicked_elsewhere {
+                self.context_menu = None;
+            }
+        }
+
+        /* Find the connection the dragged node would be inserted into */
+        let node_drop_target = self.ongoing_node_drag.and_then(|node_id| {
+            let node_rect = *node_rects.get(&node_id)?;
+            self.graph.iter_connections().find_map(|(input, output)| {
+                if self.is_connection_locked(input, output) {
+                    return None;
+                }
+                let (node_input, node_output) =
+                    self.graph.find_insertion_ports(node_id, input, output)?;
+                let src_pos = port_locations[&AnyParameterId::Output(output)];
+                let dst_pos = port_locations[&AnyParameterId::Input(input)];
+                let routing = wire_routing(&self.graph, output, user_state, &style);
+                connection_intersects_rect(src_pos, dst_pos, node_rect, routing, &style)
+                    .then_some((input, output, node_input, node_output))
+            })
+        });
+
+        /* Find the dependencies to highlight */
+        let dependencies = if self.dependency_highlight != DependencyHighlight::Off {
+            let hovered_node = if cursor_in_editor && !cursor_in_finder {
+                self.node_order.iter().rev().copied().find(|node_id| {
+                    node_rects
+                        .get(node_id)
+                        .map(|rect| rect.contains(cursor_pos))
+                        .unwrap_or(false)
+                })
+            } else {
+                None
+            };
+            let focused_nodes = match hovered_node {
+                Some(node_id) => vec![node_id],
+                None => self.selected_nodes.clone(),
+            };
+            (!focused_nodes.is_empty()).then(|| {
+                HighlightedDependencies::cached(
+                    ui.ctx(),
+                    ui.id(),
+                    &self.graph,
+                    &focused_nodes,
+                    self.dependency_highlight,
+                )
+            })
+        } else {
+            None
+        };
+        if let Some(dependencies) = &dependencies {
+            for (node_id, overlay) in node_overlays {
+                if let Some(rect) = node_rects.get(&node_id) {
+                    if !dependencies.contains_node(node_id) {
+                        ui.painter().set(
+                            overlay,
+                            Shape::rect_filled(
+                                rect.expand2(vec2(style.port_radius, 0.0)),
+                                4.0,
+                                style.dimmed_node_overlay,
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+
         /* Draw connections */
         if let Some((_, ref locator)) = self.connection_in_progress {
             let port_type = self.graph.any_param_type(*locator).unwrap();
             let connection_color = port_type.data_type_color(user_state);
+            let wire = Wire::new(port_type, user_state, &style);
             let start_pos = port_locations[locator];
 
             // Find a port to connect to
@@ -281,18 +538,91 @@ where
                     start_pos,
                 ),
             };
-            draw_connection(ui.painter(), src_pos, dst_pos, connection_color);
+            draw_connection(
+                ui.painter(),
+                src_pos,
+                dst_pos,
+                connection_color,
+                wire,
+                &style,
+            );
         }
 
+        let time = ui.input(|i| i.time);
+        let mut animating_connections = false;
+        let mut connection_labels = Vec::new();
         for (input, output) in self.graph.iter_connections() {
             let port_type = self
                 .graph
                 .any_param_type(AnyParameterId::Output(output))
                 .unwrap();
-            let connection_color = port_type.data_type_color(user_state);
+            let mut connection_color = port_type.data_type_color(user_state);
+            let wire = Wire::new(port_type, user_state, &style);
             let src_pos = port_locations[&AnyParameterId::Output(output)];
             let dst_pos = port_locations[&AnyParameterId::Input(input)];
-            draw_connection(ui.painter(), src_pos, dst_pos, connection_color);
+            let related = dependencies.as_ref().map(|dependencies| {
+                dependencies.contains_connection(self.graph[output].node, self.graph[input].node)
+            });
+            if related == Some(false) {
+                connection_color = connection_color.linear_multiply(style.dimmed_wire_opacity);
+            }
+            let annotation = self.connection_annotations.get(&(input, output));
+            let [r, g, b, _] = style.wire_highlight.to_array();
+            if self.selected_connections.contains(&(input, output)) {
+                let color = Color32::from_rgba_unmultiplied(r, g, b, 220);
+                draw_connection_highlight(ui.painter(), src_pos, dst_pos, color, wire, &style);
+            } else if hovered_connection == Some((input, output))
+                || node_drop_target.map(|(i, o, ..)| (i, o)) == Some((input, output))
+            {
+                let color = Color32::from_rgba_unmultiplied(r, g, b, 120);
+                draw_connection_highlight(ui.painter(), src_pos, dst_pos, color, wire, &style);
+            } else if let Some(color) = annotation.and_then(|a| a.highlight) {
+                draw_connection_highlight(ui.painter(), src_pos, dst_pos, color, wire, &style);
+            } else if related == Some(true) {
+                let color = Color32::from_rgba_unmultiplied(r, g, b, 80);
+                draw_connection_highlight(ui.painter(), src_pos, dst_pos, color, wire, &style);
+            }
+            draw_connection(
+                ui.painter(),
+                src_pos,
+                dst_pos,
+                connection_color,
+                wire,
+                &style,
+            );
+
+            let default_flow_speed = if self.show_data_flow {
+                style.flow_speed
+            } else {
+                0.0
+            };
+            let flow_speed = annotation
+                .and_then(|a| a.flow_speed)
+                .unwrap_or(default_flow_speed);
+            let label = annotation.and_then(|a| a.label.as_ref());
+            if flow_speed > 0.0 || label.is_some() {
+                let polyline = connection_polyline(src_pos, dst_pos, wire.routing, &style);
+                if flow_speed > 0.0 {
+                    draw_flow_dots(ui.painter(), &polyline, time as f32 * flow_speed, &style);
+                    animating_connections = true;
+                }
+                if let Some(label) = label {
+                    let length = polyline_length(&polyline);
+                    if let Some(pos) = points_along_polyline(&polyline, length / 2.0, length)
+                        .first()
+                        .copied()
+                    {
+                        connection_labels.push((pos, label.clone()));
+                    }
+                }
+            }
+        }
+        // Labels go on top of all the wires.
+        for (pos, label) in connection_labels {
+            draw_connection_label(ui.painter(), pos, label, &style);
+        }
+        if animating_connections {
+            ui.ctx().request_repaint();
         }
 
         /* Handle responses from drawing nodes */
@@ -314,6 +644,11 @@ where
                 }
                 NodeResponse::SelectNode(node_id) => {
                     self.selected_nodes = Vec::from([*node_id]);
+                    self.selected_connections.clear();
+                }
+                NodeResponse::SelectConnection { input, output } => {
+                    self.selected_connections = Vec::from([(*input, *output)]);
+                    self.selected_nodes.clear();
                 }
                 NodeResponse::DeleteNodeUi(node_id) => {
                     let (node, disc_events) = self.graph.remove_node(*node_id);
@@ -331,6 +666,10 @@ where
                         node,
                     });
                     self.node_positions.remove(*node_id);
+                    self.node_sizes.remove(*node_id);
+                    self.collapsed_nodes.remove(node_id);
+                    self.locked_nodes.remove(node_id);
+                    self.stable_ids.remove(*node_id);
                     // Make sure to not leave references to old nodes hanging
                     self.selected_nodes.retain(|id| *id != *node_id);
                     self.node_order.retain(|id| *id != *node_id);
@@ -350,17 +689,28 @@ where
                     self.node_order.remove(old_pos);
                     self.node_order.push(*node_id);
                 }
+                NodeResponse::CollapseNode { node, collapsed } => {
+                    if *collapsed {
+                        self.collapsed_nodes.insert(*node);
+                    } else {
+                        self.collapsed_nodes.remove(node);
+                    }
+                }
                 NodeResponse::MoveNode { node, drag_delta } => {
+                    self.ongoing_node_drag = Some(*node);
                     self.node_positions[*node] += *drag_delta;
                     // Handle multi-node selection movement
                     if self.selected_nodes.contains(node) && self.selected_nodes.len() > 1 {
                         for n in self.selected_nodes.iter().copied() {
-                            if n != *node {
+                            if n != *node && !self.locked_nodes.contains(&n) {
                                 self.node_positions[n] += *drag_delta;
                             }
                         }
                     }
                 }
+                NodeResponse::ResizeNode { node, size } => {
+                    self.node_sizes.insert(*node, *size);
+                }
                 NodeResponse::User(_) => {
                     // These are handled by the user code.
                 }
@@ -373,20 +723,18 @@ where
         // Handle box selection
         if let Some(box_start) = self.ongoing_box_selection {
             let selection_rect = Rect::from_two_pos(cursor_pos, box_start);
-            let bg_color = Color32::from_rgba_unmultiplied(200, 200, 200, 20);
-            let stroke_color = Color32::from_rgba_unmultiplied(200, 200, 200, 180);
             ui.painter().rect(
                 selection_rect,
                 2.0,
-                bg_color,
-                Stroke::new(3.0, stroke_color),
+                style.box_selection_fill,
+                Stroke::new(3.0, style.box_selection_stroke),
             );
 
             self.selected_nodes = node_rects
-                .into_iter()
+                .iter()
                 .filter_map(|(node_id, rect)| {
-                    if selection_rect.intersects(rect) {
-                        Some(node_id)
+                    if selection_rect.intersects(*rect) {
+                        Some(*node_id)
                     } else {
                         None
                     }
@@ -394,6 +742,26 @@ where
                 .collect();
         }
 
+        // Delete the selected connections when pressing the Delete key, unless
+        // some widget (e.g. a text field) is using the keyboard.
+        if ui.ctx().input(|i| i.key_pressed(Key::Delete))
+            && ui.ctx().memory(|mem| mem.focus().is_none())
+        {
+            removed_connections.extend(
+                self.selected_connections
+                    .iter()
+                    .copied()
+                    .filter(|(input, output)| !self.is_connection_locked(*input, *output)),
+            );
+            self.selected_connections.clear();
+        }
+        for (input, output) in removed_connections {
+            if self.graph.connection(input) == Some(output) {
+                self.graph.remove_connection(input);
+                extra_responses.push(NodeResponse::DisconnectEvent { input, output });
+            }
+        }
+
         // Push any responses that were generated during response handling.
         // These are only informative for the end-user and need no special
         // treatment here.
@@ -408,11 +776,118 @@ where
             self.connection_in_progress = None;
         }
 
-        if mouse.secondary_released() && cursor_in_editor && !cursor_in_finder {
-            self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+        // Dropping a node on top of a connection splices it in between.
+        if mouse.any_released() && self.ongoing_node_drag.is_some() {
+            if let Some((input, output, node_input, node_output)) = node_drop_target {
+                self.graph.remove_connection(input);
+                self.graph.add_connection(output, node_input);
+                self.graph.add_connection(node_output, input);
+                delayed_responses.extend([
+                    NodeResponse::DisconnectEvent { input, output },
+                    NodeResponse::ConnectEventEnded {
+                        output,
+                        input: node_input,
+                    },
+                    NodeResponse::ConnectEventEnded {
+                        output: node_output,
+                        input,
+                    },
+                ]);
+            }
+            self.ongoing_node_drag = None;
+        }
+
+        // Knife gesture: Cuts all connections crossed while right-dragging
+        // with the command modifier held.
+        let mut knife_cut_finished = false;
+        if mouse.secondary_pressed()
+            && !self.read_only
+            && ui.ctx().input(|i| i.modifiers.command)
+            && cursor_in_editor
+            && !cursor_in_finder
+        {
+            self.ongoing_knife_cut = Some(vec![cursor_pos]);
+        }
+        if let Some(mut knife_path) = self.ongoing_knife_cut.take() {
+            if let Some(last) = knife_path.last() {
+                if last.distance(cursor_pos) > 2.0 {
+                    knife_path.push(cursor_pos);
+                }
+            }
+            ui.painter().add(Shape::dashed_line(
+                &knife_path,
+                Stroke::new(2.0, style.knife),
+                6.0,
+                4.0,
+            ));
+
+            if mouse.secondary_released() {
+                let cut_connections: Vec<_> = self
+                    .graph
+                    .iter_connections()
+                    .filter(|(input, output)| {
+                        if self.is_connection_locked(*input, *output) {
+                            return false;
+                        }
+                        let src_pos = port_locations[&AnyParameterId::Output(*output)];
+                        let dst_pos = port_locations[&AnyParameterId::Input(*input)];
+                        let routing = wire_routing(&self.graph, *output, user_state, &style);
+                        polylines_intersect(
+                            &connection_polyline(src_pos, dst_pos, routing, &style),
+                            &knife_path,
+                        )
+                    })
+                    .collect();
+                for (input, output) in cut_connections {
+                    self.graph.remove_connection(input);
+                    delayed_responses.push(NodeResponse::DisconnectEvent { input, output });
+                }
+                knife_cut_finished = true;
+            } else {
+                self.ongoing_knife_cut = Some(knife_path);
+            }
+        }
+
+        if mouse.secondary_released()
+            && cursor_in_editor
+            && !cursor_in_finder
+            && !knife_cut_finished
+            && !click_on_connection
+        {
+            // Right-clicking a port or a node opens its context menu, while
+            // right-clicking anywhere else opens the node finder.
+            let clicked_port = port_locations
+                .iter()
+                .map(|(param, pos)| (*param, pos.distance(cursor_pos)))
+                .filter(|(_, distance)| *distance < DISTANCE_TO_CONNECT)
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(param, _)| ContextMenuTarget::Port(param));
+            let clicked_node = self
+                .node_order
+                .iter()
+                .rev()
+                .find(|node_id| {
+                    node_rects
+                        .get(node_id)
+                        .map(|rect| rect.contains(cursor_pos))
+                        .unwrap_or(false)
+                })
+                .map(|node_id| ContextMenuTarget::Node(*node_id));
+
+            if let Some(target) = clicked_port.or(clicked_node) {
+                self.node_finder = None;
+                self.context_menu = Some(ContextMenu {
+                    target,
+                    position: cursor_pos,
+                });
+            } else if !self.read_only {
+                self.node_finder = Some(NodeFinder::new_at(cursor_pos));
+                self.context_menu = None;
+            }
         }
         if ui.ctx().input(|i| i.key_pressed(Key::Escape)) {
             self.node_finder = None;
+            self.context_menu = None;
         }
 
         if r.dragged() && ui.ctx().input(|i| i.pointer.middle_down()) {
@@ -421,8 +896,12 @@ where
 
         // Deselect and deactivate finder if the editor backround is clicked,
         // *or* if the the mouse clicks off the ui
-        if click_on_background || (mouse.any_click() && !cursor_in_editor) {
+        if (click_on_background && !click_on_connection) || (mouse.any_click() && !cursor_in_editor)
+        {
             self.selected_nodes = Vec::new();
+            self.selected_connections = Vec::new();
+        }
+        if click_on_background || (mouse.any_click() && !cursor_in_editor) {
             self.node_finder = None;
         }
 
@@ -439,28 +918,671 @@ where
             cursor_in_finder,
         }
     }
+
+    /// Draws the entries of the context menu for `target`. Returns whether the
+    /// menu should be closed.
+    fn context_menu_ui(
+        &mut self,
+        ui: &mut Ui,
+        target: ContextMenuTarget,
+        user_state: &mut UserState,
+        responses: &mut Vec<NodeResponse<UserResponse, NodeData>>,
+        removed_connections: &mut Vec<(InputId, OutputId)>,
+    ) -> bool {
+        let mut should_close = false;
+        let user_responses = match target {
+            ContextMenuTarget::Connection { input, output } => {
+                if self.graph.connection(input) != Some(output) {
+                    return true;
+                }
+                let locked = self.is_connection_locked(input, output);
+                if ui
+                    .add_enabled(!locked, Button::new("Delete connection"))
+                    .clicked()
+                {
+                    removed_connections.push((input, output));
+                    should_close = true;
+                }
+                let node_id = self.graph[input].node;
+                self.graph[node_id].user_data.connection_context_menu(
+                    ui,
+                    node_id,
+                    input,
+                    output,
+                    &self.graph,
+                    user_state,
+                )
+            }
+            ContextMenuTarget::Node(node_id) => {
+                if !self.graph.nodes.contains_key(node_id) {
+                    return true;
+                }
+                let locked = self.is_node_locked(node_id);
+                let duplicate_node =
+                    self.graph[node_id]
+                        .user_data
+                        .duplicate_node(node_id, &self.graph, user_state);
+                if let Some(duplicate_node) = duplicate_node {
+                    if ui
+                        .add_enabled(!self.read_only, Button::new("Duplicate"))
+                        .clicked()
+                    {
+                        let new_node = duplicate_node(&mut self.graph, node_id);
+                        self.place_duplicate(node_id, new_node);
+                        responses.push(NodeResponse::CreatedNode(new_node));
+                        should_close = true;
+                    }
+                }
+                let collapsed = self.collapsed_nodes.contains(&node_id);
+                if ui
+                    .button(if collapsed { "Expand" } else { "Collapse" })
+                    .clicked()
+                {
+                    responses.push(NodeResponse::CollapseNode {
+                        node: node_id,
+                        collapsed: !collapsed,
+                    });
+                    should_close = true;
+                }
+                if ui
+                    .add_enabled(!locked, Button::new("Disconnect all"))
+                    .clicked()
+                {
+                    removed_connections.extend(self.graph.iter_connections().filter(
+                        |(input, output)| {
+                            (self.graph[*input].node == node_id
+                                || self.graph[*output].node == node_id)
+                                && !self.is_connection_locked(*input, *output)
+                        },
+                    ));
+                    should_close = true;
+                }
+                let can_delete =
+                    self.graph[node_id]
+                        .user_data
+                        .can_delete(node_id, &self.graph, user_state);
+                if can_delete && ui.add_enabled(!locked, Button::new("Delete")).clicked() {
+                    responses.push(NodeResponse::DeleteNodeUi(node_id));
+                    should_close = true;
+                }
+                self.graph[node_id].user_data.context_menu(
+                    ui,
+                    node_id,
+                    None,
+                    &self.graph,
+                    user_state,
+                )
+            }
+            ContextMenuTarget::Port(param) => {
+                let node_id = match param {
+                    AnyParameterId::Input(input) => self.graph.try_get_input(input).map(|p| p.node),
+                    AnyParameterId::Output(output) => {
+                        self.graph.try_get_output(output).map(|p| p.node)
+                    }
+                };
+                let node_id = match node_id {
+                    Some(node_id) => node_id,
+                    None => return true,
+                };
+                if ui
+                    .add_enabled(!self.is_node_locked(node_id), Button::new("Disconnect"))
+                    .clicked()
+                {
+                    removed_connections.extend(self.graph.iter_connections().filter(
+                        |(input, output)| {
+                            let connected = match param {
+                                AnyParameterId::Input(param) => *input == param,
+                                AnyParameterId::Output(param) => *output == param,
+                            };
+                            connected && !self.is_connection_locked(*input, *output)
+                        },
+                    ));
+                    should_close = true;
+                }
+                self.graph[node_id].user_data.context_menu(
+                    ui,
+                    node_id,
+                    Some(param),
+                    &self.graph,
+                    user_state,
+                )
+            }
+        };
+
+        if !user_responses.is_empty() {
+            should_close = true;
+        }
+        responses.extend(user_responses);
+        should_close
+    }
 }
 
-fn draw_connection(painter: &Painter, src_pos: Pos2, dst_pos: Pos2, color: Color32) {
-    let connection_stroke = egui::Stroke { width: 5.0, color };
+/// The nodes highlighted by [`GraphEditorState::dependency_highlight`]. Both
+/// sets contain the focused nodes.
+#[derive(Clone)]
+struct HighlightedDependencies {
+    upstream: HashSet<NodeId>,
+    downstream: HashSet<NodeId>,
+}
+
+/// The last [`HighlightedDependencies`] of an editor, stored in egui memory
+/// and reused while the focused nodes and the connections don't change.
+#[derive(Clone)]
+struct DependencyMemory {
+    focused_nodes: Vec<NodeId>,
+    mode: DependencyHighlight,
+    connections_hash: u64,
+    dependencies: HighlightedDependencies,
+}
 
-    let control_scale = ((dst_pos.x - src_pos.x) / 2.0).max(30.0);
+impl HighlightedDependencies {
+    fn new<NodeData, DataType, ValueType>(
+        graph: &Graph<NodeData, DataType, ValueType>,
+        focused_nodes: &[NodeId],
+        mode: DependencyHighlight,
+    ) -> Self {
+        let mut upstream: HashSet<NodeId> = focused_nodes.iter().copied().collect();
+        let mut downstream = upstream.clone();
+        if matches!(
+            mode,
+            DependencyHighlight::Upstream | DependencyHighlight::Both
+        ) {
+            let adjacency = graph.node_adjacency(true);
+            for node_id in focused_nodes.iter().copied() {
+                upstream.extend(reachable_nodes(&adjacency, node_id));
+            }
+        }
+        if matches!(
+            mode,
+            DependencyHighlight::Downstream | DependencyHighlight::Both
+        ) {
+            let adjacency = graph.node_adjacency(false);
+            for node_id in focused_nodes.iter().copied() {
+                downstream.extend(reachable_nodes(&adjacency, node_id));
+            }
+        }
+        Self {
+            upstream,
+            downstream,
+        }
+    }
+
+    /// Like [`Self::new`], but reuses the dependencies computed in a previous
+    /// frame when neither the focused nodes nor the connections changed.
+    fn cached<NodeData, DataType, ValueType>(
+        ctx: &Context,
+        editor_id: Id,
+        graph: &Graph<NodeData, DataType, ValueType>,
+        focused_nodes: &[NodeId],
+        mode: DependencyHighlight,
+    ) -> Self {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for connection in graph.iter_connections() {
+            connection.hash(&mut hasher);
+        }
+        let connections_hash = hasher.finish();
+
+        let id = editor_id.with("dependency_highlight");
+        let memory = ctx.memory_mut(|mem| mem.data.get_temp::<DependencyMemory>(id));
+        if let Some(memory) = memory {
+            if memory.focused_nodes == focused_nodes
+                && memory.mode == mode
+                && memory.connections_hash == connections_hash
+            {
+                return memory.dependencies;
+            }
+        }
+
+        let dependencies = Self::new(graph, focused_nodes, mode);
+        let memory = DependencyMemory {
+            focused_nodes: focused_nodes.to_vec(),
+            mode,
+            connections_hash,
+            dependencies: dependencies.clone(),
+        };
+        ctx.memory_mut(|mem| mem.data.insert_temp(id, memory));
+        dependencies
+    }
+
+    fn contains_node(&self, node_id: NodeId) -> bool {
+        self.upstream.contains(&node_id) || self.downstream.contains(&node_id)
+    }
+
+    /// Whether the connection between these nodes is part of the highlighted
+    /// dependencies.
+    fn contains_connection(&self, src_node: NodeId, dst_node: NodeId) -> bool {
+        (self.upstream.contains(&src_node) && self.upstream.contains(&dst_node))
+            || (self.downstream.contains(&src_node) && self.downstream.contains(&dst_node))
+    }
+}
+
+/// How the wire of a connection is drawn, as chosen by its data type.
+#[derive(Clone, Copy, Debug)]
+struct Wire {
+    routing: WireRouting,
+    dashed: bool,
+}
+
+impl Wire {
+    fn new<UserState>(
+        data_type: &impl DataTypeTrait<UserState>,
+        user_state: &mut UserState,
+        style: &GraphStyle,
+    ) -> Self {
+        Self {
+            routing: data_type
+                .wire_routing(user_state)
+                .unwrap_or(style.wire_routing),
+            dashed: data_type.wire_dashed(user_state),
+        }
+    }
+}
+
+/// The routing of the wire leaving `output`.
+fn wire_routing<NodeData, DataType, ValueType, UserState>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    output: OutputId,
+    user_state: &mut UserState,
+    style: &GraphStyle,
+) -> WireRouting
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    graph[output]
+        .typ
+        .wire_routing(user_state)
+        .unwrap_or(style.wire_routing)
+}
+
+fn draw_connection(
+    painter: &Painter,
+    src_pos: Pos2,
+    dst_pos: Pos2,
+    color: Color32,
+    wire: Wire,
+    style: &GraphStyle,
+) {
+    let connection_stroke = egui::Stroke {
+        width: style.wire_width,
+        color,
+    };
+    if wire.dashed {
+        painter.add(Shape::dashed_line(
+            &connection_polyline(src_pos, dst_pos, wire.routing, style),
+            connection_stroke,
+            style.wire_dash_length,
+            style.wire_gap_length,
+        ));
+    } else {
+        painter.add(connection_shape(
+            src_pos,
+            dst_pos,
+            connection_stroke,
+            wire.routing,
+            style,
+        ));
+    }
+}
+
+/// Draws a wide halo below a connection to signal it is hovered, selected or
+/// the target of some ongoing interaction.
+fn draw_connection_highlight(
+    painter: &Painter,
+    src_pos: Pos2,
+    dst_pos: Pos2,
+    color: Color32,
+    wire: Wire,
+    style: &GraphStyle,
+) {
+    let highlight_stroke = egui::Stroke {
+        width: style.wire_width + 4.0,
+        color,
+    };
+    painter.add(connection_shape(
+        src_pos,
+        dst_pos,
+        highlight_stroke,
+        wire.routing,
+        style,
+    ));
+}
+
+/// Draws the data flow dots along a wire. `offset` is the distance travelled
+/// by the dots so far.
+fn draw_flow_dots(painter: &Painter, polyline: &[Pos2], offset: f32, style: &GraphStyle) {
+    let spacing = style.flow_dot_spacing.max(1.0);
+    for pos in points_along_polyline(polyline, offset.rem_euclid(spacing), spacing) {
+        painter.circle_filled(pos, style.flow_dot_radius, style.flow_dot_color);
+    }
+}
+
+fn draw_connection_label(painter: &Painter, pos: Pos2, label: String, style: &GraphStyle) {
+    let padding = vec2(4.0, 2.0);
+    let galley = painter.layout_no_wrap(label, FontId::proportional(11.0), style.node_text);
+    let rect = Rect::from_center_size(pos, galley.size()).expand2(padding);
+    painter.rect_filled(rect, 3.0, style.node_background);
+    painter.galley(rect.min + padding, galley);
+}
+
+fn connection_shape(
+    src_pos: Pos2,
+    dst_pos: Pos2,
+    stroke: Stroke,
+    routing: WireRouting,
+    style: &GraphStyle,
+) -> Shape {
+    match routing {
+        WireRouting::Bezier => connection_bezier(src_pos, dst_pos, stroke, style).into(),
+        WireRouting::Straight | WireRouting::Orthogonal => Shape::line(
+            connection_polyline(src_pos, dst_pos, routing, style),
+            stroke,
+        ),
+    }
+}
+
+pub(crate) fn connection_bezier(
+    src_pos: Pos2,
+    dst_pos: Pos2,
+    stroke: Stroke,
+    style: &GraphStyle,
+) -> CubicBezierShape {
+    let control_scale = ((dst_pos.x - src_pos.x) / 2.0).max(30.0) * style.wire_curvature;
     let src_control = src_pos + Vec2::X * control_scale;
     let dst_control = dst_pos - Vec2::X * control_scale;
 
-    let bezier = CubicBezierShape::from_points_stroke(
+    CubicBezierShape::from_points_stroke(
         [src_pos, src_control, dst_control, dst_pos],
         false,
         Color32::TRANSPARENT,
-        connection_stroke,
-    );
+        stroke,
+    )
+}
+
+/// Approximates the path of a connection with a list of line segments, so it
+/// can be used for hit-testing.
+pub(crate) fn connection_polyline(
+    src_pos: Pos2,
+    dst_pos: Pos2,
+    routing: WireRouting,
+    style: &GraphStyle,
+) -> Vec<Pos2> {
+    match routing {
+        WireRouting::Bezier => {
+            connection_bezier(src_pos, dst_pos, Stroke::NONE, style).flatten(Some(0.5))
+        }
+        WireRouting::Straight => vec![src_pos, dst_pos],
+        WireRouting::Orthogonal => round_corners(
+            &orthogonal_path(src_pos, dst_pos, style.wire_corner_radius),
+            style.wire_corner_radius,
+        ),
+    }
+}
 
-    painter.add(bezier);
+/// The corners of an orthogonal wire. Wires going backwards leave the output
+/// and enter the input horizontally, going around through the middle.
+fn orthogonal_path(src_pos: Pos2, dst_pos: Pos2, corner_radius: f32) -> Vec<Pos2> {
+    let stub = 2.0 * corner_radius.max(5.0);
+    if dst_pos.x - src_pos.x >= 2.0 * stub {
+        let mid_x = (src_pos.x + dst_pos.x) / 2.0;
+        vec![
+            src_pos,
+            pos2(mid_x, src_pos.y),
+            pos2(mid_x, dst_pos.y),
+            dst_pos,
+        ]
+    } else {
+        let mid_y = (src_pos.y + dst_pos.y) / 2.0;
+        vec![
+            src_pos,
+            pos2(src_pos.x + stub, src_pos.y),
+            pos2(src_pos.x + stub, mid_y),
+            pos2(dst_pos.x - stub, mid_y),
+            pos2(dst_pos.x - stub, dst_pos.y),
+            dst_pos,
+        ]
+    }
+}
+
+/// Replaces the inner corners of a polyline with quadratic arcs of up to
+/// `radius`, shrinking them when the adjacent segments are too short.
+fn round_corners(points: &[Pos2], radius: f32) -> Vec<Pos2> {
+    const CORNER_SEGMENTS: usize = 6;
+
+    let mut result = Vec::with_capacity(points.len() * (CORNER_SEGMENTS + 1));
+    if let Some(first) = points.first() {
+        result.push(*first);
+    }
+    for corner in points.windows(3) {
+        let (prev, curr, next) = (corner[0], corner[1], corner[2]);
+        let r = radius
+            .min(prev.distance(curr) / 2.0)
+            .min(curr.distance(next) / 2.0);
+        if r <= 0.0 {
+            result.push(curr);
+            continue;
+        }
+        let start = curr + (prev - curr).normalized() * r;
+        let end = curr + (next - curr).normalized() * r;
+        for i in 0..=CORNER_SEGMENTS {
+            let t = i as f32 / CORNER_SEGMENTS as f32;
+            let a = start + (curr - start) * t;
+            let b = curr + (end - curr) * t;
+            result.push(a + (b - a) * t);
+        }
+    }
+    if points.len() > 1 {
+        result.push(*points.last().unwrap());
+    }
+    result
+}
+
+fn connection_intersects_rect(
+    src_pos: Pos2,
+    dst_pos: Pos2,
+    rect: Rect,
+    routing: WireRouting,
+    style: &GraphStyle,
+) -> bool {
+    connection_polyline(src_pos, dst_pos, routing, style)
+        .windows(2)
+        .any(|segment| segment_intersects_rect(segment[0], segment[1], rect))
+}
+
+fn segment_intersects_rect(a: Pos2, b: Pos2, rect: Rect) -> bool {
+    if rect.contains(a) || rect.contains(b) {
+        return true;
+    }
+    let corners = [
+        rect.left_top(),
+        rect.right_top(),
+        rect.right_bottom(),
+        rect.left_bottom(),
+    ];
+    (0..4).any(|i| segments_intersect(a, b, corners[i], corners[(i + 1) % 4]))
+}
+
+fn polyline_length(polyline: &[Pos2]) -> f32 {
+    polyline
+        .windows(2)
+        .map(|segment| segment[0].distance(segment[1]))
+        .sum()
+}
+
+/// The points along a polyline at distances `first`, `first + spacing`,
+/// `first + 2 * spacing`... from its start.
+fn points_along_polyline(polyline: &[Pos2], first: f32, spacing: f32) -> Vec<Pos2> {
+    let mut points = Vec::new();
+    let mut next = first;
+    let mut travelled = 0.0;
+    for segment in polyline.windows(2) {
+        let length = segment[0].distance(segment[1]);
+        while next <= travelled + length && length > 0.0 {
+            let t = (next - travelled) / length;
+            points.push(segment[0] + (segment[1] - segment[0]) * t);
+            next += spacing;
+        }
+        travelled += length;
+    }
+    points
+}
+
+fn distance_to_polyline(point: Pos2, polyline: &[Pos2]) -> f32 {
+    polyline
+        .windows(2)
+        .map(|segment| distance_to_segment(point, segment[0], segment[1]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+fn distance_to_segment(point: Pos2, a: Pos2, b: Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() > 0.0 {
+        ((point - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + ab * t)
+}
+
+fn polylines_intersect(a: &[Pos2], b: &[Pos2]) -> bool {
+    a.windows(2).any(|sa| {
+        b.windows(2)
+            .any(|sb| segments_intersect(sa[0], sa[1], sb[0], sb[1]))
+    })
+}
+
+/// Whether segment `a`-`b` crosses segment `c`-`d`.
+fn segments_intersect(a: Pos2, b: Pos2, c: Pos2, d: Pos2) -> bool {
+    let cross = |o: Pos2, p: Pos2, q: Pos2| (p - o).x * (q - o).y - (p - o).y * (q - o).x;
+    let side_a = cross(c, d, a);
+    let side_b = cross(c, d, b);
+    let side_c = cross(a, b, c);
+    let side_d = cross(a, b, d);
+    (side_a > 0.0) != (side_b > 0.0) && (side_c > 0.0) != (side_d > 0.0)
+}
+
+/// The contents of the tooltip shown when hovering a port: its name, its
+/// datatype and, for inputs, where its value comes from.
+fn port_tooltip<NodeData, DataType, ValueType, UserState>(
+    ui: &mut Ui,
+    graph: &Graph<NodeData, DataType, ValueType>,
+    param_id: AnyParameterId,
+) where
+    DataType: DataTypeTrait<UserState>,
+{
+    ui.strong(graph.param_name(param_id).unwrap_or_default());
+    if let Ok(typ) = graph.any_param_type(param_id) {
+        ui.label(RichText::new(typ.name()).weak());
+    }
+    if let AnyParameterId::Input(input) = param_id {
+        match graph.connection(input) {
+            Some(output) => {
+                let source_node = &graph[graph[output].node];
+                let source_param = graph
+                    .param_name(AnyParameterId::Output(output))
+                    .unwrap_or_default();
+                ui.label(format!("From: {} › {}", source_node.label, source_param));
+            }
+            None => {
+                ui.label("Not connected");
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 struct OuterRectMemory(Rect);
 
+/// The rect and port locations of a node the last time it was drawn, relative
+/// to the node position. Used to estimate the metrics of nodes that are not
+/// drawn because they are outside the editor.
+#[derive(Clone, Debug)]
+pub(crate) struct NodeLayoutMemory {
+    pub(crate) rect: Rect,
+    pub(crate) titlebar_height: f32,
+    pub(crate) ports: Vec<(AnyParameterId, Vec2)>,
+}
+
+impl NodeLayoutMemory {
+    fn id(editor_id: Id, node_id: NodeId) -> Id {
+        editor_id.with((node_id, "layout"))
+    }
+
+    /// The stored layout of a node drawn by the editor with the given `Ui` id.
+    pub(crate) fn load(ctx: &Context, editor_id: Id, node_id: NodeId) -> Option<Self> {
+        ctx.memory_mut(|mem| mem.data.get_temp(Self::id(editor_id, node_id)))
+    }
+
+    fn store(self, ctx: &Context, editor_id: Id, node_id: NodeId) {
+        ctx.memory_mut(|mem| mem.data.insert_temp(Self::id(editor_id, node_id), self));
+    }
+
+    fn add_to_layout(&self, layout: &mut GraphLayout, node_id: NodeId, position: Pos2) {
+        layout
+            .node_rects
+            .insert(node_id, self.rect.translate(position.to_vec2()));
+        layout
+            .titlebar_heights
+            .insert(node_id, self.titlebar_height);
+        for (param, offset) in &self.ports {
+            layout.port_locations.insert(*param, position + *offset);
+        }
+    }
+
+    /// Fills in the rect and port locations of the node, as if it was drawn at
+    /// `node_pos`. Ports without a stored location, like those added since
+    /// the node was last drawn, are placed at the middle of the node sides.
+    fn estimate_metrics<NodeData>(
+        &self,
+        node: &Node<NodeData>,
+        node_pos: Pos2,
+        port_locations: &mut PortLocations,
+        node_rects: &mut NodeRects,
+    ) {
+        let rect = self.rect.translate(node_pos.to_vec2());
+        node_rects.insert(node.id, rect);
+
+        let stored_location = |param: AnyParameterId| {
+            self.ports
+                .iter()
+                .find(|(stored, _)| *stored == param)
+                .map(|(_, offset)| node_pos + *offset)
+        };
+        for input in node.input_ids() {
+            let param = AnyParameterId::Input(input);
+            let location = stored_location(param).unwrap_or_else(|| rect.left_center());
+            port_locations.insert(param, location);
+        }
+        for output in node.output_ids() {
+            let param = AnyParameterId::Output(output);
+            let location = stored_location(param).unwrap_or_else(|| rect.right_center());
+            port_locations.insert(param, location);
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+{
+    /// Where the nodes and ports were drawn in the last frame, e.g. to export
+    /// the graph with [`Self::to_svg`]. `editor_id` is the id of the `Ui`
+    /// given to [`Self::draw_graph_editor`]. Nodes that were never drawn are
+    /// missing from the layout.
+    pub fn layout(&self, ctx: &Context, editor_id: Id) -> GraphLayout {
+        let mut layout = GraphLayout::default();
+        for node_id in &self.node_order {
+            if let (Some(memory), Some(position)) = (
+                NodeLayoutMemory::load(ctx, editor_id, *node_id),
+                self.node_positions.get(*node_id),
+            ) {
+                memory.add_to_layout(&mut layout, *node_id, *position);
+            }
+        }
+        layout
+    }
+}
+
 impl<'a, NodeData, DataType, ValueType, UserResponse, UserState>
     GraphNodeWidget<'a, NodeData, DataType, ValueType>
 where
@@ -475,20 +1597,36 @@ where
         WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>,
     DataType: DataTypeTrait<UserState>,
 {
-    pub const MAX_NODE_SIZE: [f32; 2] = [200.0, 200.0];
+    #[deprecated(
+        note = "use `NodeSizeConstraints::DEFAULT_PREFERRED_SIZE`, or `NodeDataTrait::size_constraints` to size nodes"
+    )]
+    pub const MAX_NODE_SIZE: [f32; 2] = [
+        NodeSizeConstraints::DEFAULT_PREFERRED_SIZE.x,
+        NodeSizeConstraints::DEFAULT_PREFERRED_SIZE.y,
+    ];
 
     pub fn show(
         self,
         ui: &mut Ui,
         user_state: &mut UserState,
     ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let constraints = self.graph[self.node_id]
+            .user_data
+            .size_constraints(self.node_id, self.graph, user_state)
+            .normalized();
+        let size = self
+            .size
+            .map(|size| size.clamp(constraints.min_size, constraints.max_size))
+            .unwrap_or(constraints.preferred_size);
+
         let mut child_ui = ui.child_ui_with_id_source(
-            Rect::from_min_size(*self.position + self.pan, Self::MAX_NODE_SIZE.into()),
+            Rect::from_min_size(*self.position + self.pan, size),
             Layout::default(),
             self.node_id,
         );
 
-        Self::show_graph_node(self, &mut child_ui, user_state)
+        let editor_id = ui.id();
+        Self::show_graph_node(self, &mut child_ui, editor_id, user_state, constraints)
     }
 
     /// Draws this node. Also fills in the list of port locations with all of its ports.
@@ -496,22 +1634,15 @@ where
     fn show_graph_node(
         self,
         ui: &mut Ui,
+        editor_id: Id,
         user_state: &mut UserState,
+        constraints: NodeSizeConstraints,
     ) -> Vec<NodeResponse<UserResponse, NodeData>> {
         let margin = egui::vec2(15.0, 5.0);
         let mut responses = Vec::<NodeResponse<UserResponse, NodeData>>::new();
 
-        let background_color;
-        let text_color;
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#ffffff").unwrap();
-            text_color = color_from_hex("#505050").unwrap();
-        }
-
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+        let style = self.style;
+        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, style.node_text);
 
         // Preallocate shapes to paint below contents
         let outline_shape = ui.painter().add(Shape::Noop);
@@ -526,6 +1657,15 @@ where
         inner_rect.max.y = inner_rect.max.y.max(inner_rect.min.y);
 
         let mut child_ui = ui.child_ui(inner_rect, *ui.layout());
+        // Resized nodes fill all their space, others shrink to fit their
+        // contents but never below the minimum size.
+        if !self.collapsed {
+            if self.size.is_some() {
+                child_ui.set_min_size(inner_rect.size());
+            } else {
+                child_ui.set_min_size((constraints.min_size - margin * 2.0).max(Vec2::ZERO));
+            }
+        }
 
         // Get interaction rect from memory, it may expand after the window response on resize.
         let interaction_rect = ui
@@ -554,19 +1694,24 @@ where
                 ui.add(Label::new(
                     RichText::new(&self.graph[self.node_id].label)
                         .text_style(TextStyle::Button)
-                        .color(text_color),
+                        .color(style.node_text),
+                ));
+                responses.extend(self.graph[self.node_id].user_data.top_bar_ui(
+                    ui,
+                    self.node_id,
+                    self.graph,
+                    user_state,
                 ));
-                responses.extend(
-                    self.graph[self.node_id]
-                        .user_data
-                        .top_bar_ui(ui, self.node_id, self.graph, user_state)
-                        .into_iter(),
-                );
                 ui.add_space(8.0); // The size of the little cross icon
+                ui.add_space(18.0); // The size of the collapse toggle
             });
             ui.add_space(margin.y);
             title_height = ui.min_size().y;
 
+            if self.collapsed {
+                return;
+            }
+
             // First pass: Draw the inner fields. Compute port heights
             let inputs = self.graph[self.node_id].inputs.clone();
             for (param_name, param_id) in inputs {
@@ -581,27 +1726,29 @@ where
                     // Default, but results in a totally safe alternative.
                     let mut value = std::mem::take(&mut self.graph[param_id].value);
 
-                    if self.graph.connection(param_id).is_some() {
-                        let node_responses = value.value_widget_connected(
-                            &param_name,
-                            self.node_id,
-                            ui,
-                            user_state,
-                            &self.graph[self.node_id].user_data,
-                        );
-
-                        responses.extend(node_responses.into_iter().map(NodeResponse::User));
-                    } else {
-                        let node_responses = value.value_widget(
-                            &param_name,
-                            self.node_id,
-                            ui,
-                            user_state,
-                            &self.graph[self.node_id].user_data,
-                        );
-
-                        responses.extend(node_responses.into_iter().map(NodeResponse::User));
-                    }
+                    // Locked nodes show their values, but can't edit them.
+                    let node_responses = ui
+                        .add_enabled_ui(!self.locked, |ui| {
+                            if self.graph.connection(param_id).is_some() {
+                                value.value_widget_connected(
+                                    &param_name,
+                                    self.node_id,
+                                    ui,
+                                    user_state,
+                                    &self.graph[self.node_id].user_data,
+                                )
+                            } else {
+                                value.value_widget(
+                                    &param_name,
+                                    self.node_id,
+                                    ui,
+                                    user_state,
+                                    &self.graph[self.node_id].user_data,
+                                )
+                            }
+                        })
+                        .inner;
+                    responses.extend(node_responses.into_iter().map(NodeResponse::User));
 
                     self.graph[self.node_id].user_data.separator(
                         ui,
@@ -621,12 +1768,13 @@ where
             let outputs = self.graph[self.node_id].outputs.clone();
             for (param_name, param_id) in outputs {
                 let height_before = ui.min_rect().bottom();
-                responses.extend(
-                    self.graph[self.node_id]
-                        .user_data
-                        .output_ui(ui, self.node_id, self.graph, user_state, &param_name)
-                        .into_iter(),
-                );
+                responses.extend(self.graph[self.node_id].user_data.output_ui(
+                    ui,
+                    self.node_id,
+                    self.graph,
+                    user_state,
+                    &param_name,
+                ));
 
                 self.graph[self.node_id].user_data.separator(
                     ui,
@@ -640,12 +1788,12 @@ where
                 output_port_heights.push((height_before + height_after) / 2.0);
             }
 
-            responses.extend(
-                self.graph[self.node_id]
-                    .user_data
-                    .bottom_ui(ui, self.node_id, self.graph, user_state)
-                    .into_iter(),
-            );
+            responses.extend(self.graph[self.node_id].user_data.bottom_ui(
+                ui,
+                self.node_id,
+                self.graph,
+                user_state,
+            ));
         });
 
         // Second pass, iterate again to draw the ports. This happens outside
@@ -673,6 +1821,8 @@ where
             port_locations: &mut PortLocations,
             ongoing_drag: Option<(NodeId, AnyParameterId)>,
             is_connected_input: bool,
+            locked: bool,
+            style: &GraphStyle,
         ) where
             DataType: DataTypeTrait<UserState>,
             UserResponse: UserResponseTrait,
@@ -682,7 +1832,7 @@ where
 
             let port_rect = Rect::from_center_size(port_pos, egui::vec2(10.0, 10.0));
 
-            let sense = if ongoing_drag.is_some() {
+            let sense = if ongoing_drag.is_some() || locked {
                 Sense::hover()
             } else {
                 Sense::click_and_drag()
@@ -698,14 +1848,32 @@ where
             };
 
             let port_color = if close_enough {
-                Color32::WHITE
+                style.port_hover
             } else {
                 port_type.data_type_color(user_state)
             };
-            ui.painter()
-                .circle(port_rect.center(), 5.0, port_color, Stroke::NONE);
+            let optional = match param_id {
+                AnyParameterId::Input(input) => {
+                    matches!(graph[input].kind(), InputParamKind::ConnectionOrConstant)
+                }
+                AnyParameterId::Output(_) => false,
+            };
+            let hollow = port_type.port_hollow(optional, user_state);
+            port_type.port_shape(user_state).paint(
+                ui.painter(),
+                port_rect.center(),
+                style.port_radius,
+                port_color,
+                hollow,
+            );
 
-            if resp.drag_started() {
+            let resp = if ongoing_drag.is_none() {
+                resp.on_hover_ui(|ui| port_tooltip(ui, graph, param_id))
+            } else {
+                resp
+            };
+
+            if resp.drag_started_by(PointerButton::Primary) && !locked {
                 if is_connected_input {
                     let input = param_id.assume_input();
                     let corresp_output = graph
@@ -724,6 +1892,7 @@ where
                 if origin_node != node_id {
                     // Don't allow self-loops
                     if graph.any_param_type(origin_param).unwrap() == port_type
+                        && !locked
                         && close_enough
                         && ui.input(|i| i.pointer.any_released())
                     {
@@ -741,11 +1910,29 @@ where
             port_locations.insert(param_id, port_rect.center());
         }
 
+        // Collapsed nodes don't draw any parameters. Their connected ports are
+        // spread along the edges of the title bar instead, and can't be
+        // interacted with.
+        if self.collapsed {
+            for (param_id, port_pos) in collapsed_ports(self.graph, self.node_id, outer_rect) {
+                let port_type = self.graph.any_param_type(param_id).unwrap();
+                let port_color = port_type.data_type_color(user_state);
+                port_type.port_shape(user_state).paint(
+                    ui.painter(),
+                    port_pos,
+                    style.port_radius,
+                    port_color,
+                    false,
+                );
+                self.port_locations.insert(param_id, port_pos);
+            }
+        }
+
         // Input ports
         for ((_, param), port_height) in self.graph[self.node_id]
             .inputs
             .iter()
-            .zip(input_port_heights.into_iter())
+            .zip(input_port_heights)
         {
             let should_draw = match self.graph[*param].kind() {
                 InputParamKind::ConnectionOnly => true,
@@ -766,6 +1953,8 @@ where
                     self.port_locations,
                     self.ongoing_drag,
                     self.graph.connection(*param).is_some(),
+                    self.locked,
+                    style,
                 );
             }
         }
@@ -774,7 +1963,7 @@ where
         for ((_, param), port_height) in self.graph[self.node_id]
             .outputs
             .iter()
-            .zip(output_port_heights.into_iter())
+            .zip(output_port_heights)
         {
             let pos_right = pos2(port_right, port_height);
             draw_port(
@@ -788,6 +1977,8 @@ where
                 self.port_locations,
                 self.ongoing_drag,
                 false,
+                self.locked,
+                style,
             );
         }
 
@@ -795,11 +1986,20 @@ where
         // NOTE: This code is a bit more involved than it needs to be because egui
         // does not support drawing rectangles with asymmetrical round corners.
 
+        let status =
+            self.graph[self.node_id]
+                .user_data
+                .status(self.node_id, self.graph, user_state);
+
         let (shape, outline) = {
             let rounding_radius = 4.0;
             let rounding = Rounding::same(rounding_radius);
 
-            let titlebar_height = title_height + margin.y;
+            let titlebar_height = if self.collapsed {
+                outer_rect.height()
+            } else {
+                title_height + margin.y
+            };
             let titlebar_rect =
                 Rect::from_min_size(outer_rect.min, vec2(outer_rect.width(), titlebar_height));
             let titlebar = Shape::Rect(RectShape {
@@ -808,7 +2008,7 @@ where
                 fill: self.graph[self.node_id]
                     .user_data
                     .titlebar_color(ui, self.node_id, self.graph, user_state)
-                    .unwrap_or_else(|| background_color.lighten(0.8)),
+                    .unwrap_or(style.node_titlebar),
                 stroke: Stroke::NONE,
             });
 
@@ -819,7 +2019,7 @@ where
             let body = Shape::Rect(RectShape {
                 rect: body_rect,
                 rounding: Rounding::none(),
-                fill: background_color,
+                fill: style.node_background,
                 stroke: Stroke::NONE,
             });
 
@@ -830,47 +2030,111 @@ where
             let bottom_body = Shape::Rect(RectShape {
                 rect: bottom_body_rect,
                 rounding,
-                fill: background_color,
+                fill: style.node_background,
                 stroke: Stroke::NONE,
             });
 
-            let node_rect = titlebar_rect.union(body_rect).union(bottom_body_rect);
-            let outline = if self.selected {
-                Shape::Rect(RectShape {
+            let (node_rect, shapes) = if self.collapsed {
+                (titlebar_rect, vec![titlebar])
+            } else {
+                (
+                    titlebar_rect.union(body_rect).union(bottom_body_rect),
+                    vec![titlebar, body, bottom_body],
+                )
+            };
+            let mut outline = Vec::new();
+            if let Some(status) = &status {
+                outline.push(Shape::Rect(RectShape {
+                    rect: node_rect.expand(2.5),
+                    rounding,
+                    fill: style.status_color(status.kind),
+                    stroke: Stroke::NONE,
+                }));
+            }
+            if self.selected {
+                outline.push(Shape::Rect(RectShape {
                     rect: node_rect.expand(1.0),
                     rounding,
-                    fill: Color32::WHITE.lighten(0.8),
+                    fill: style.selection_outline,
                     stroke: Stroke::NONE,
-                })
-            } else {
-                Shape::Noop
-            };
+                }));
+            }
 
             // Take note of the node rect, so the editor can use it later to compute intersections.
             self.node_rects.insert(self.node_id, node_rect);
 
-            (Shape::Vec(vec![titlebar, body, bottom_body]), outline)
+            (Shape::Vec(shapes), outline)
         };
 
         ui.painter().set(background_shape, shape);
-        ui.painter().set(outline_shape, outline);
+        ui.painter().set(outline_shape, Shape::Vec(outline));
+
+        // Remember the layout, in case the node is culled in later frames.
+        let node_pos = *self.position + self.pan;
+        let node = &self.graph[self.node_id];
+        NodeLayoutMemory {
+            rect: self.node_rects[&self.node_id].translate(-node_pos.to_vec2()),
+            titlebar_height: if self.collapsed {
+                self.node_rects[&self.node_id].height()
+            } else {
+                title_height + margin.y
+            },
+            ports: node
+                .input_ids()
+                .map(AnyParameterId::Input)
+                .chain(node.output_ids().map(AnyParameterId::Output))
+                .filter_map(|param| Some((param, *self.port_locations.get(&param)? - node_pos)))
+                .collect(),
+        }
+        .store(ui.ctx(), editor_id, self.node_id);
 
         // --- Interaction ---
 
+        if let Some(status) = &status {
+            Self::status_badge(
+                ui,
+                self.node_id,
+                self.node_rects[&self.node_id],
+                status,
+                style,
+            );
+        }
+
         // Titlebar buttons
-        let can_delete = self.graph.nodes[self.node_id].user_data.can_delete(
-            self.node_id,
-            self.graph,
-            user_state,
-        );
+        let can_delete = !self.locked
+            && self.graph.nodes[self.node_id].user_data.can_delete(
+                self.node_id,
+                self.graph,
+                user_state,
+            );
 
-        if can_delete && Self::close_button(ui, outer_rect).clicked() {
+        if can_delete && Self::close_button(ui, outer_rect, style).clicked() {
             responses.push(NodeResponse::DeleteNodeUi(self.node_id));
         };
 
+        // Resizing
+        if !self.collapsed && !self.locked {
+            let resize_response = Self::resize_handle(ui, self.node_id, outer_rect, style);
+            let drag_delta = resize_response.drag_delta();
+            if drag_delta.length_sq() > 0.0 {
+                responses.push(NodeResponse::ResizeNode {
+                    node: self.node_id,
+                    size: (outer_rect.size() + drag_delta)
+                        .clamp(constraints.min_size, constraints.max_size),
+                });
+            }
+        }
+
+        if Self::collapse_button(ui, outer_rect, self.collapsed, can_delete, style).clicked() {
+            responses.push(NodeResponse::CollapseNode {
+                node: self.node_id,
+                collapsed: !self.collapsed,
+            });
+        }
+
         // Movement
         let drag_delta = window_response.drag_delta();
-        if drag_delta.length_sq() > 0.0 {
+        if drag_delta.length_sq() > 0.0 && !self.locked {
             responses.push(NodeResponse::MoveNode {
                 node: self.node_id,
                 drag_delta,
@@ -890,7 +2154,7 @@ where
         responses
     }
 
-    fn close_button(ui: &mut Ui, node_rect: Rect) -> Response {
+    fn close_button(ui: &mut Ui, node_rect: Rect, style: &GraphStyle) -> Response {
         // Measurements
         let margin = 8.0;
         let size = 10.0;
@@ -901,30 +2165,9 @@ where
         let rect = Rect::from_center_size(position, vec2(size, size));
         let resp = ui.allocate_rect(rect, Sense::click());
 
-        let dark_mode = ui.visuals().dark_mode;
-        let color = if resp.clicked() {
-            if dark_mode {
-                color_from_hex("#ffffff").unwrap()
-            } else {
-                color_from_hex("#000000").unwrap()
-            }
-        } else if resp.hovered() {
-            if dark_mode {
-                color_from_hex("#dddddd").unwrap()
-            } else {
-                color_from_hex("#222222").unwrap()
-            }
-        } else {
-            #[allow(clippy::collapsible_else_if)]
-            if dark_mode {
-                color_from_hex("#aaaaaa").unwrap()
-            } else {
-                color_from_hex("#555555").unwrap()
-            }
-        };
         let stroke = Stroke {
             width: stroke_width,
-            color,
+            color: Self::titlebar_button_color(&resp, style),
         };
 
         ui.painter()
@@ -934,4 +2177,124 @@ where
 
         resp
     }
+
+    /// The little triangle used to collapse and expand the node. It is placed
+    /// to the left of the close button, or in its place when there is none.
+    fn collapse_button(
+        ui: &mut Ui,
+        node_rect: Rect,
+        collapsed: bool,
+        has_close_button: bool,
+        style: &GraphStyle,
+    ) -> Response {
+        // Measurements
+        let margin = 8.0;
+        let size = 10.0;
+        let offs = margin + size / 2.0;
+        let close_button_offs = if has_close_button { size + margin } else { 0.0 };
+
+        let position = pos2(
+            node_rect.right() - offs - close_button_offs,
+            node_rect.top() + offs,
+        );
+        let rect = Rect::from_center_size(position, vec2(size, size));
+        let resp = ui.allocate_rect(rect, Sense::click());
+
+        let points = if collapsed {
+            // Pointing right
+            vec![rect.left_top(), rect.right_center(), rect.left_bottom()]
+        } else {
+            // Pointing down
+            vec![rect.left_top(), rect.right_top(), rect.center_bottom()]
+        };
+        ui.painter().add(Shape::convex_polygon(
+            points,
+            Self::titlebar_button_color(&resp, style),
+            Stroke::NONE,
+        ));
+
+        resp
+    }
+
+    /// The badge drawn on the top-left corner of nodes with a status. Shows the
+    /// status message when hovered.
+    fn status_badge(
+        ui: &mut Ui,
+        node_id: NodeId,
+        node_rect: Rect,
+        status: &NodeStatus,
+        style: &GraphStyle,
+    ) {
+        // Measurements
+        let radius = 7.0;
+
+        let center = node_rect.left_top() + vec2(2.0, 2.0);
+        let rect = Rect::from_center_size(center, Vec2::splat(radius * 2.0));
+        let resp = ui.interact(rect, Id::new((node_id, "status")), Sense::hover());
+
+        let icon = match status.kind {
+            NodeStatusKind::Error => "✖",
+            NodeStatusKind::Warning => "!",
+            NodeStatusKind::Running => "…",
+            NodeStatusKind::Cached => "✔",
+        };
+        ui.painter()
+            .circle_filled(center, radius, style.status_color(status.kind));
+        ui.painter().text(
+            center,
+            Align2::CENTER_CENTER,
+            icon,
+            FontId::proportional(radius * 1.4),
+            Color32::WHITE,
+        );
+
+        let message = status
+            .message
+            .clone()
+            .unwrap_or_else(|| format!("{:?}", status.kind));
+        resp.on_hover_text(message);
+    }
+
+    /// The grip in the bottom-right corner of the node, used to resize it.
+    fn resize_handle(
+        ui: &mut Ui,
+        node_id: NodeId,
+        node_rect: Rect,
+        style: &GraphStyle,
+    ) -> Response {
+        // Measurements
+        let size = 10.0;
+        let stroke_width = 1.5;
+
+        let rect = Rect::from_min_max(node_rect.right_bottom() - vec2(size, size), node_rect.max);
+        let resp = ui
+            .interact(rect, Id::new((node_id, "resize")), Sense::drag())
+            .on_hover_cursor(CursorIcon::ResizeNwSe);
+
+        let stroke = Stroke {
+            width: stroke_width,
+            color: Self::titlebar_button_color(&resp, style),
+        };
+        for offs in [3.0, 7.0] {
+            ui.painter().line_segment(
+                [
+                    pos2(rect.right() - offs, rect.bottom() - 2.0),
+                    pos2(rect.right() - 2.0, rect.bottom() - offs),
+                ],
+                stroke,
+            );
+        }
+
+        resp
+    }
+
+    fn titlebar_button_color(resp: &Response, style: &GraphStyle) -> Color32 {
+        if resp.clicked() {
+            style.close_button_clicked
+        } else if resp.hovered() {
+            style.close_button_hovered
+        } else {
+            style.close_button
+        }
+    }
 }
@@ -8,3 +8,12 @@ pub enum EguiGraphError {
     #[error("Parameter {0:?} was not found in the graph.")]
     InvalidParameterId(AnyParameterId),
 }
+
+/// Returned by the `try_to_*` accessors of `#[derive(WidgetValue)]` enums
+/// when the value is of another variant.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Expected a value of kind {expected}, found {found}")]
+pub struct WrongValueKind {
+    pub expected: &'static str,
+    pub found: &'static str,
+}
@@ -0,0 +1,242 @@
+use super::*;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Escapes the characters with a special meaning in the labels of DOT record
+/// nodes.
+fn escape_dot_record(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Escapes the characters that would end a quoted Mermaid label.
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+/// The identifier used for each node in the exported text, numbered in the
+/// given order.
+fn export_ids(nodes: impl Iterator<Item = NodeId>) -> HashMap<NodeId, String> {
+    nodes
+        .enumerate()
+        .map(|(i, node_id)| (node_id, format!("n{i}")))
+        .collect()
+}
+
+fn write_dot<NodeData, DataType, ValueType, UserState>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    nodes: &[NodeId],
+    positions: Option<&SecondaryMap<NodeId, egui::Pos2>>,
+) -> String
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    let ids = export_ids(nodes.iter().copied());
+    let param_field =
+        |name: &str, typ: &DataType| escape_dot_record(&format!("{name}: {}", typ.name()));
+
+    let mut dot = String::new();
+    writeln!(dot, "digraph {{").unwrap();
+    writeln!(dot, "    rankdir=LR;").unwrap();
+    writeln!(dot, "    node [shape=record];").unwrap();
+    for node_id in nodes {
+        let node = &graph[*node_id];
+        // With `rankdir=LR`, every level of braces flips the direction of
+        // the fields: Inputs, title and outputs go side by side, and the
+        // ports of each group are stacked.
+        let mut fields = Vec::new();
+        if !node.inputs.is_empty() {
+            let inputs = (node.inputs.iter().enumerate())
+                .map(|(i, (name, id))| format!("<i{i}> {}", param_field(name, &graph[*id].typ)))
+                .collect::<Vec<_>>();
+            fields.push(format!("{{{}}}", inputs.join("|")));
+        }
+        fields.push(escape_dot_record(&node.label));
+        if !node.outputs.is_empty() {
+            let outputs = (node.outputs.iter().enumerate())
+                .map(|(i, (name, id))| format!("<o{i}> {}", param_field(name, &graph[*id].typ)))
+                .collect::<Vec<_>>();
+            fields.push(format!("{{{}}}", outputs.join("|")));
+        }
+        write!(
+            dot,
+            "    {} [label=\"{{{}}}\"",
+            ids[node_id],
+            fields.join("|")
+        )
+        .unwrap();
+        if let Some(pos) = positions.and_then(|positions| positions.get(*node_id)) {
+            // DOT coordinates are in points, with the y axis pointing up.
+            write!(dot, ", pos=\"{},{}!\"", pos.x, -pos.y).unwrap();
+        }
+        writeln!(dot, "];").unwrap();
+    }
+
+    for (input, output) in graph.iter_connections() {
+        let (input_node, output_node) = (graph[input].node, graph[output].node);
+        let input_port = (graph[input_node].input_ids()).position(|id| id == input);
+        let output_port = (graph[output_node].output_ids()).position(|id| id == output);
+        if let (Some(input_id), Some(output_id), Some(i), Some(o)) = (
+            ids.get(&input_node),
+            ids.get(&output_node),
+            input_port,
+            output_port,
+        ) {
+            writeln!(dot, "    {output_id}:o{o}:e -> {input_id}:i{i}:w;").unwrap();
+        }
+    }
+    writeln!(dot, "}}").unwrap();
+    dot
+}
+
+fn write_mermaid<NodeData, DataType, ValueType, UserState>(
+    graph: &Graph<NodeData, DataType, ValueType>,
+    nodes: &[NodeId],
+    positions: Option<&SecondaryMap<NodeId, egui::Pos2>>,
+) -> String
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    let ids = export_ids(nodes.iter().copied());
+
+    let mut mermaid = String::new();
+    writeln!(mermaid, "flowchart LR").unwrap();
+    for node_id in nodes {
+        let label = escape_mermaid(&graph[*node_id].label);
+        writeln!(mermaid, "    {}[\"{label}\"]", ids[node_id]).unwrap();
+        // Mermaid lays out the chart by itself, so positions can only be
+        // kept as comments.
+        if let Some(pos) = positions.and_then(|positions| positions.get(*node_id)) {
+            writeln!(mermaid, "    %% {} at ({}, {})", ids[node_id], pos.x, pos.y).unwrap();
+        }
+    }
+    for (input, output) in graph.iter_connections() {
+        let (input_node, output_node) = (graph[input].node, graph[output].node);
+        let (input_id, output_id) = match (ids.get(&input_node), ids.get(&output_node)) {
+            (Some(input_id), Some(output_id)) => (input_id, output_id),
+            _ => continue,
+        };
+        let name = |param: AnyParameterId| graph.param_name(param).unwrap_or_default();
+        let label = escape_mermaid(&format!(
+            "{} → {}: {}",
+            name(output.into()),
+            name(input.into()),
+            graph[output].typ.name()
+        ));
+        writeln!(mermaid, "    {output_id} -->|\"{label}\"| {input_id}").unwrap();
+    }
+    mermaid
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Exports the graph in the Graphviz DOT language. Nodes are records with
+    /// one port per parameter, labeled with its name and
+    /// [`DataTypeTrait::name`]. When given, node positions are written as
+    /// pinned `pos` attributes, which are honored by `neato -n`.
+    pub fn to_dot<UserState>(&self, positions: Option<&SecondaryMap<NodeId, egui::Pos2>>) -> String
+    where
+        DataType: DataTypeTrait<UserState>,
+    {
+        let nodes: Vec<_> = self.iter_nodes().collect();
+        write_dot(self, &nodes, positions)
+    }
+
+    /// Exports the graph as a Mermaid flowchart. Connections are labeled with
+    /// the names of their parameters and their data type. Mermaid doesn't
+    /// support fixed positions, so the given positions are written as
+    /// comments.
+    pub fn to_mermaid<UserState>(
+        &self,
+        positions: Option<&SecondaryMap<NodeId, egui::Pos2>>,
+    ) -> String
+    where
+        DataType: DataTypeTrait<UserState>,
+    {
+        let nodes: Vec<_> = self.iter_nodes().collect();
+        write_mermaid(self, &nodes, positions)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    /// The nodes in drawing order, skipping stale entries of the node order.
+    pub(crate) fn ordered_nodes(&self) -> Vec<NodeId> {
+        (self.node_order.iter().copied())
+            .filter(|node_id| self.graph.nodes.contains_key(*node_id))
+            .collect()
+    }
+
+    /// Exports the graph in the Graphviz DOT language, see [`Graph::to_dot`].
+    /// Nodes are listed in drawing order.
+    pub fn to_dot(&self, include_positions: bool) -> String {
+        let positions = include_positions.then_some(&self.node_positions);
+        write_dot(&self.graph, &self.ordered_nodes(), positions)
+    }
+
+    /// Exports the graph as a Mermaid flowchart, see [`Graph::to_mermaid`].
+    /// Nodes are listed in drawing order.
+    pub fn to_mermaid(&self, include_positions: bool) -> String {
+        let positions = include_positions.then_some(&self.node_positions);
+        write_mermaid(&self.graph, &self.ordered_nodes(), positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[derive(PartialEq, Eq)]
+    struct Scalar;
+
+    impl DataTypeTrait<()> for Scalar {
+        fn data_type_color(&self, _user_state: &mut ()) -> egui::Color32 {
+            egui::Color32::WHITE
+        }
+
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed("scalar")
+        }
+    }
+
+    #[test]
+    fn exports_nodes_ports_and_connections() {
+        let mut graph = Graph::<(), Scalar, ()>::new();
+        let a = graph.add_node("Value".into(), (), |graph, node_id| {
+            graph.add_output_param(node_id, "out".into(), Scalar);
+        });
+        let b = graph.add_node("Add <1>".into(), (), |graph, node_id| {
+            for name in ["A", "B"] {
+                graph.add_input_param(
+                    node_id,
+                    name.into(),
+                    Scalar,
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+            }
+        });
+        let output = graph[a].get_output("out").unwrap();
+        let input = graph[b].get_input("B").unwrap();
+        graph.add_connection(output, input);
+
+        let dot = graph.to_dot::<()>(None);
+        assert!(dot.contains("n0 [label=\"{Value|{<o0> out: scalar}}\"];"));
+        assert!(dot.contains("n1 [label=\"{{<i0> A: scalar|<i1> B: scalar}|Add \\<1\\>}\"];"));
+        assert!(dot.contains("n0:o0:e -> n1:i1:w;"));
+
+        let mermaid = graph.to_mermaid::<()>(None);
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("n0 -->|\"out → B: scalar\"| n1"));
+    }
+}
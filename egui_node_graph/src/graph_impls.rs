@@ -1,4 +1,5 @@
 use super::*;
+use std::collections::{HashMap, HashSet};
 
 impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
     pub fn new() -> Self {
@@ -152,6 +153,123 @@ impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
     pub fn get_output(&self, output: OutputId) -> &OutputParam<DataType> {
         &self.outputs[output]
     }
+
+    /// All the nodes `node_id` depends on, directly or through other nodes.
+    /// Does not contain `node_id` itself, unless it is part of a cycle.
+    pub fn upstream_nodes(&self, node_id: NodeId) -> HashSet<NodeId> {
+        reachable_nodes(&self.node_adjacency(true), node_id)
+    }
+
+    /// All the nodes that depend on `node_id`, directly or through other nodes.
+    /// Does not contain `node_id` itself, unless it is part of a cycle.
+    pub fn downstream_nodes(&self, node_id: NodeId) -> HashSet<NodeId> {
+        reachable_nodes(&self.node_adjacency(false), node_id)
+    }
+
+    /// Maps every connected node to the nodes connected to its inputs when
+    /// `upstream` is set, or to its outputs otherwise. Built in a single pass
+    /// over the connections.
+    pub(crate) fn node_adjacency(&self, upstream: bool) -> HashMap<NodeId, Vec<NodeId>> {
+        let mut adjacency: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for (input, output) in self.iter_connections() {
+            let (input_node, output_node) =
+                match (self.try_get_input(input), self.try_get_output(output)) {
+                    (Some(input), Some(output)) => (input.node, output.node),
+                    _ => continue,
+                };
+            let (from, to) = if upstream {
+                (input_node, output_node)
+            } else {
+                (output_node, input_node)
+            };
+            adjacency.entry(from).or_default().push(to);
+        }
+        adjacency
+    }
+
+    /// The name of a parameter, as given when it was added to its node.
+    pub fn param_name(&self, param: AnyParameterId) -> Option<&str> {
+        match param {
+            AnyParameterId::Input(input) => {
+                let node = self.nodes.get(self.try_get_input(input)?.node)?;
+                node.inputs
+                    .iter()
+                    .find(|(_, id)| *id == input)
+                    .map(|(name, _)| name.as_str())
+            }
+            AnyParameterId::Output(output) => {
+                let node = self.nodes.get(self.try_get_output(output)?.node)?;
+                node.outputs
+                    .iter()
+                    .find(|(_, id)| *id == output)
+                    .map(|(name, _)| name.as_str())
+            }
+        }
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Adds a copy of the node with given `node_id` to the graph, including
+    /// its user data and all of its parameters, but none of its connections.
+    /// Returns the id of the new node.
+    pub fn duplicate_node(&mut self, node_id: NodeId) -> NodeId {
+        let node = self[node_id].clone();
+        self.add_node(node.label, node.user_data, |graph, new_node| {
+            for (name, input) in node.inputs {
+                let param = graph[input].clone();
+                graph.add_input_param(
+                    new_node,
+                    name,
+                    param.typ,
+                    param.value,
+                    param.kind,
+                    param.shown_inline,
+                );
+            }
+            for (name, output) in node.outputs {
+                let typ = graph[output].typ.clone();
+                graph.add_output_param(new_node, name, typ);
+            }
+        })
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Finds the ports of `node_id` that would be used to insert it into the
+    /// existing connection `output -> input`: A free input with the same type
+    /// as `output`, and a free output with the same type as `input`.
+    ///
+    /// Returns `None` if the connection touches `node_id` itself or the node
+    /// has no suitable pair of ports.
+    pub fn find_insertion_ports(
+        &self,
+        node_id: NodeId,
+        input: InputId,
+        output: OutputId,
+    ) -> Option<(InputId, OutputId)> {
+        let (conn_input, conn_output) = (self.try_get_input(input)?, self.try_get_output(output)?);
+        if conn_input.node == node_id || conn_output.node == node_id {
+            return None;
+        }
+
+        let node = self.nodes.get(node_id)?;
+        let node_input = node.input_ids().find(|id| {
+            let param = &self[*id];
+            param.typ == conn_output.typ
+                && !matches!(param.kind, InputParamKind::ConstantOnly)
+                && self.connection(*id).is_none()
+        })?;
+        let node_output = node.output_ids().find(|id| {
+            self[*id].typ == conn_input.typ && !self.connections.values().any(|o| o == id)
+        })?;
+
+        Some((node_input, node_output))
+    }
 }
 
 impl<NodeData, DataType, ValueType> Default for Graph<NodeData, DataType, ValueType> {
@@ -164,14 +282,14 @@ impl<NodeData> Node<NodeData> {
     pub fn inputs<'a, DataType, DataValue>(
         &'a self,
         graph: &'a Graph<NodeData, DataType, DataValue>,
-    ) -> impl Iterator<Item = &InputParam<DataType, DataValue>> + 'a {
+    ) -> impl Iterator<Item = &'a InputParam<DataType, DataValue>> + 'a {
         self.input_ids().map(|id| graph.get_input(id))
     }
 
     pub fn outputs<'a, DataType, DataValue>(
         &'a self,
         graph: &'a Graph<NodeData, DataType, DataValue>,
-    ) -> impl Iterator<Item = &OutputParam<DataType>> + 'a {
+    ) -> impl Iterator<Item = &'a OutputParam<DataType>> + 'a {
         self.output_ids().map(|id| graph.get_output(id))
     }
 
@@ -213,3 +331,62 @@ impl<DataType, ValueType> InputParam<DataType, ValueType> {
         self.node
     }
 }
+
+/// All the nodes reachable from `node_id` in a map built by
+/// [`Graph::node_adjacency`]. Does not contain `node_id` itself, unless it is
+/// part of a cycle.
+pub(crate) fn reachable_nodes(
+    adjacency: &HashMap<NodeId, Vec<NodeId>>,
+    node_id: NodeId,
+) -> HashSet<NodeId> {
+    let neighbours = |node| adjacency.get(&node).into_iter().flatten().copied();
+    let mut visited = HashSet::new();
+    let mut pending: Vec<NodeId> = neighbours(node_id).collect();
+    while let Some(node) = pending.pop() {
+        if visited.insert(node) {
+            pending.extend(neighbours(node));
+        }
+    }
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chain `a -> b -> c`, plus `d -> d` and an unconnected `e`.
+    fn chain() -> (Graph<(), (), ()>, [NodeId; 5]) {
+        let mut graph = Graph::new();
+        let add_node = |graph: &mut Graph<(), (), ()>| {
+            graph.add_node(String::new(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    (),
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), ());
+            })
+        };
+        let nodes = [(); 5].map(|_| add_node(&mut graph));
+        let [a, b, c, d, _] = nodes;
+        for (from, to) in [(a, b), (b, c), (d, d)] {
+            let output = graph[from].get_output("out").unwrap();
+            let input = graph[to].get_input("in").unwrap();
+            graph.add_connection(output, input);
+        }
+        (graph, nodes)
+    }
+
+    #[test]
+    fn finds_transitive_dependencies() {
+        let (graph, [a, b, c, d, e]) = chain();
+        assert_eq!(graph.upstream_nodes(c), HashSet::from([a, b]));
+        assert_eq!(graph.downstream_nodes(a), HashSet::from([b, c]));
+        assert_eq!(graph.upstream_nodes(d), HashSet::from([d]));
+        assert!(graph.upstream_nodes(e).is_empty());
+        assert!(graph.downstream_nodes(c).is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+use egui::{Color32, DragValue, Response, Ui, Vec2};
+use std::borrow::Cow;
+use std::ops::RangeInclusive;
+
+/// Settings for the widgets of [`InlineWidget`] values. Numbers ignore the
+/// settings that don't apply to them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WidgetOptions {
+    /// How much the value changes when dragging by one point.
+    pub speed: Option<f64>,
+    /// The values the user can choose.
+    pub range: Option<RangeInclusive<f64>>,
+}
+
+/// A value that can be edited with a single widget, e.g. inside
+/// [`crate::WidgetValueTrait::value_widget`]. This is what
+/// `#[derive(WidgetValue)]` uses to draw the fields of a value.
+pub trait InlineWidget {
+    fn inline_widget(&mut self, ui: &mut Ui, options: &WidgetOptions) -> Response;
+}
+
+fn drag_value<'a, Num: egui::emath::Numeric>(
+    value: &'a mut Num,
+    options: &WidgetOptions,
+) -> DragValue<'a> {
+    let mut drag_value = DragValue::new(value);
+    if let Some(speed) = options.speed {
+        drag_value = drag_value.speed(speed);
+    }
+    if let Some(range) = &options.range {
+        drag_value = drag_value.clamp_range(range.clone());
+    }
+    drag_value
+}
+
+macro_rules! impl_inline_widget_for_numbers {
+    ($($t:ty),*) => {
+        $(
+            impl InlineWidget for $t {
+                fn inline_widget(&mut self, ui: &mut Ui, options: &WidgetOptions) -> Response {
+                    ui.add(drag_value(self, options))
+                }
+            }
+        )*
+    };
+}
+
+impl_inline_widget_for_numbers!(f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl InlineWidget for bool {
+    fn inline_widget(&mut self, ui: &mut Ui, _options: &WidgetOptions) -> Response {
+        ui.checkbox(self, "")
+    }
+}
+
+impl InlineWidget for String {
+    fn inline_widget(&mut self, ui: &mut Ui, _options: &WidgetOptions) -> Response {
+        ui.text_edit_singleline(self)
+    }
+}
+
+impl InlineWidget for Vec2 {
+    fn inline_widget(&mut self, ui: &mut Ui, options: &WidgetOptions) -> Response {
+        ui.horizontal(|ui| {
+            ui.label("x");
+            let x = ui.add(drag_value(&mut self.x, options));
+            ui.label("y");
+            x | ui.add(drag_value(&mut self.y, options))
+        })
+        .inner
+    }
+}
+
+impl InlineWidget for Color32 {
+    fn inline_widget(&mut self, ui: &mut Ui, _options: &WidgetOptions) -> Response {
+        ui.color_edit_button_srgba(self)
+    }
+}
+
+/// A value picked from a fixed list with a combo box, usually a fieldless
+/// enum. Can be derived with `#[derive(ComboBoxValue)]`, which also implements
+/// [`InlineWidget`] with [`combo_box_widget`].
+pub trait ComboBoxValue: Clone + PartialEq + Sized {
+    /// The values shown in the combo box, in order.
+    fn all_values() -> Vec<Self>;
+
+    /// The text shown for this value.
+    fn label(&self) -> Cow<'_, str>;
+}
+
+/// Draws a combo box to pick one of [`ComboBoxValue::all_values`].
+pub fn combo_box_widget<T: ComboBoxValue>(value: &mut T, ui: &mut Ui) -> Response {
+    egui::ComboBox::from_id_source(ui.next_auto_id())
+        .selected_text(value.label().into_owned())
+        .show_ui(ui, |ui| {
+            for option in T::all_values() {
+                let label = option.label().into_owned();
+                ui.selectable_value(value, option, label);
+            }
+        })
+        .response
+}
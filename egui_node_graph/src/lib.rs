@@ -2,6 +2,9 @@
 
 use slotmap::{SecondaryMap, SlotMap};
 
+/// The version of egui used by this library
+pub use egui;
+
 pub type SVec<T> = smallvec::SmallVec<[T; 4]>;
 
 /// Contains the main definitions for the node graph model.
@@ -23,6 +26,14 @@ pub mod graph_impls;
 pub mod error;
 pub use error::*;
 
+/// Checks and repairs the invariants of graphs, e.g. after deserializing them
+pub mod validation;
+pub use validation::*;
+
+/// A fluent API to build graphs in code, connecting ports by name
+pub mod builder;
+pub use builder::*;
+
 /// The main struct in the library, contains all the necessary state to draw the
 /// UI graph
 pub mod ui_state;
@@ -32,6 +43,33 @@ pub use ui_state::*;
 pub mod node_finder;
 pub use node_finder::*;
 
+/// Colors and measurements used to draw the graph editor
+pub mod style;
+pub use style::*;
+
+/// A versioned save format, allowing applications to migrate old documents
+#[cfg(feature = "persistence")]
+pub mod persistence;
+#[cfg(feature = "persistence")]
+pub use persistence::*;
+
+/// A human-readable graph format, independent of the slotmap keys
+#[cfg(feature = "persistence")]
+pub mod document;
+#[cfg(feature = "persistence")]
+pub use document::*;
+
+/// Exports graphs to the Graphviz DOT and Mermaid text formats
+pub mod export;
+
+/// Renders graphs as SVG images
+pub mod svg;
+
+/// Conversions to petgraph graphs, and implementations of the petgraph visit
+/// traits for `Graph`
+#[cfg(feature = "petgraph")]
+pub mod petgraph_impls;
+
 /// The inner details of the egui implementation. Most egui code lives here.
 pub mod editor_ui;
 pub use editor_ui::*;
@@ -41,6 +79,19 @@ pub use editor_ui::*;
 pub mod traits;
 pub use traits::*;
 
+/// Widgets to edit common value types, used by `#[derive(WidgetValue)]`
+pub mod inline_widget;
+pub use inline_widget::*;
+
+/// A headless harness to test the editor by scripting input events.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+/// Derives `NodeTemplateTrait`, `WidgetValueTrait` and `ComboBoxValue`, see
+/// the `egui_node_graph_derive` crate
+#[cfg(feature = "derive")]
+pub use egui_node_graph_derive::{ComboBoxValue, NodeTemplate, WidgetValue};
+
 mod utils;
 
 mod color_hex_utils;
@@ -1,6 +1,6 @@
 use std::{collections::BTreeMap, marker::PhantomData};
 
-use crate::{color_hex_utils::*, CategoryTrait, NodeTemplateIter, NodeTemplateTrait};
+use crate::{CategoryTrait, DataTypeTrait, GraphStyle, NodeTemplateIter, NodeTemplateTrait};
 
 use egui::*;
 
@@ -14,10 +14,15 @@ pub struct NodeFinder<NodeTemplate> {
     _phantom: PhantomData<NodeTemplate>,
 }
 
-impl<NodeTemplate, NodeData, UserState, CategoryType> NodeFinder<NodeTemplate>
+impl<NodeTemplate, NodeData, DataType, UserState, CategoryType> NodeFinder<NodeTemplate>
 where
-    NodeTemplate:
-        NodeTemplateTrait<NodeData = NodeData, UserState = UserState, CategoryType = CategoryType>,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState>,
     CategoryType: CategoryTrait,
 {
     pub fn new_at(pos: Pos2) -> Self {
@@ -37,22 +42,12 @@ where
         ui: &mut Ui,
         all_kinds: impl NodeTemplateIter<Item = NodeTemplate>,
         user_state: &mut UserState,
+        style: &GraphStyle,
     ) -> Option<NodeTemplate> {
-        let background_color;
-        let text_color;
-
-        if ui.visuals().dark_mode {
-            background_color = color_from_hex("#3f3f3f").unwrap();
-            text_color = color_from_hex("#fefefe").unwrap();
-        } else {
-            background_color = color_from_hex("#fefefe").unwrap();
-            text_color = color_from_hex("#3f3f3f").unwrap();
-        }
-
-        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, text_color);
+        ui.visuals_mut().widgets.noninteractive.fg_stroke = Stroke::new(2.0, style.finder_text);
 
         let frame = Frame::dark_canvas(ui.style())
-            .fill(background_color)
+            .fill(style.finder_background)
             .inner_margin(vec2(5.0, 5.0));
 
         // The archetype that will be returned.
@@ -117,9 +112,10 @@ where
                                             .open(update_open.then_some(default_open))
                                             .show(ui, |ui| {
                                                 for (kind, kind_name) in filtered_kinds {
-                                                    if ui
-                                                        .selectable_label(false, kind_name)
-                                                        .clicked()
+                                                    if Self::kind_label(
+                                                        ui, kind, kind_name, user_state,
+                                                    )
+                                                    .clicked()
                                                     {
                                                         submitted_archetype = Some(kind.clone());
                                                     } else if query_submit {
@@ -134,7 +130,7 @@ where
                                 for kind in orphan_kinds {
                                     let kind_name = kind.node_finder_label(user_state).to_string();
 
-                                    if ui.selectable_label(false, kind_name).clicked() {
+                                    if Self::kind_label(ui, kind, kind_name, user_state).clicked() {
                                         submitted_archetype = Some(kind.clone());
                                     } else if query_submit {
                                         submitted_archetype = Some(kind.clone());
@@ -148,4 +144,60 @@ where
 
         submitted_archetype
     }
+
+    /// Draws the selectable entry for a node template. Hovering it shows the
+    /// template description and the ports it will create, when available.
+    fn kind_label(
+        ui: &mut Ui,
+        kind: &NodeTemplate,
+        kind_name: String,
+        user_state: &mut UserState,
+    ) -> Response {
+        ui.selectable_label(false, kind_name).on_hover_ui(|ui| {
+            Self::kind_preview(ui, kind, user_state);
+        })
+    }
+
+    fn kind_preview(ui: &mut Ui, kind: &NodeTemplate, user_state: &mut UserState) {
+        let description = kind
+            .node_finder_description(user_state)
+            .map(|d| d.into_owned());
+        let signature = kind.node_finder_signature(user_state);
+
+        if description.is_none() && signature.is_none() {
+            ui.label(kind.node_finder_label(user_state));
+            return;
+        }
+
+        ui.set_max_width(250.0);
+        ui.strong(kind.node_finder_label(user_state));
+        if let Some(description) = description {
+            ui.label(description);
+        }
+
+        if let Some(signature) = signature {
+            for (header, params) in [("Inputs", signature.inputs), ("Outputs", signature.outputs)] {
+                if params.is_empty() {
+                    continue;
+                }
+                ui.separator();
+                ui.label(RichText::new(header).small());
+                for (name, typ) in params {
+                    ui.horizontal(|ui| {
+                        let (rect, _) = ui.allocate_exact_size(vec2(10.0, 10.0), Sense::hover());
+                        let color = typ.data_type_color(user_state);
+                        typ.port_shape(user_state).paint(
+                            ui.painter(),
+                            rect.center(),
+                            5.0,
+                            color,
+                            false,
+                        );
+                        ui.label(name);
+                        ui.label(RichText::new(typ.name()).weak());
+                    });
+                }
+            }
+        }
+    }
 }
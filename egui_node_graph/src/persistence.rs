@@ -0,0 +1,299 @@
+use super::*;
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// The version of the [`SaveEnvelope`] layout written by this library. It
+/// changes when the structs of this library are serialized differently.
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// The version of this library, as stored in new [`SaveEnvelope`]s.
+pub const LIBRARY_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Errors that can happen when saving or loading a [`SaveEnvelope`].
+#[derive(Debug, thiserror::Error)]
+pub enum SaveError {
+    #[error("The save format version {0} is not supported by this version of the library")]
+    UnsupportedFormatVersion(u32),
+
+    #[error("The document uses schema version {found}, newer than the supported {supported}")]
+    NewerSchemaVersion { found: u32, supported: u32 },
+
+    #[error("Could not migrate the document from schema version {from_version}: {message}")]
+    Migration { from_version: u32, message: String },
+
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Lets an application upgrade documents written with older versions of its
+/// own types (node data, data types, values, templates...) before they are
+/// deserialized.
+///
+/// Bump [`SaveMigration::schema_version`] every time a change to these types
+/// breaks old files, and teach [`SaveMigration::migrate`] how to upgrade a
+/// document from the previous version.
+pub trait SaveMigration {
+    /// The schema version of the documents written by the application.
+    fn schema_version(&self) -> u32;
+
+    /// Upgrades a document from schema version `from_version` to
+    /// `from_version + 1`, in place. When loading a document, this is called
+    /// once for every version between the one stored in the document and the
+    /// current one.
+    fn migrate(&self, from_version: u32, document: &mut serde_json::Value) -> Result<(), String>;
+}
+
+/// No migrations: The schema is at version `0` and never changes.
+impl SaveMigration for () {
+    fn schema_version(&self) -> u32 {
+        0
+    }
+
+    fn migrate(&self, from_version: u32, _document: &mut serde_json::Value) -> Result<(), String> {
+        Err(format!("No migration from version {from_version}"))
+    }
+}
+
+/// A saved document together with the versions it was written with, so it
+/// can be migrated when read by a newer version of the application.
+///
+/// The document is kept as a generic JSON value, but the envelope itself can
+/// be stored with any serde format.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveEnvelope {
+    /// The layout version of the structs of this library, see
+    /// [`SAVE_FORMAT_VERSION`].
+    pub format_version: u32,
+    /// The version of this library that wrote the document. Informative only.
+    pub library_version: String,
+    /// The schema version of the application types, see
+    /// [`SaveMigration::schema_version`].
+    pub schema_version: u32,
+    /// The serialized value.
+    pub document: serde_json::Value,
+}
+
+impl SaveEnvelope {
+    /// Wraps `value`, tagging it with the current versions.
+    pub fn new<T: Serialize>(value: &T, migration: &impl SaveMigration) -> Result<Self, SaveError> {
+        Ok(Self {
+            format_version: SAVE_FORMAT_VERSION,
+            library_version: LIBRARY_VERSION.to_owned(),
+            schema_version: migration.schema_version(),
+            document: serde_json::to_value(value)?,
+        })
+    }
+
+    /// Runs the migrations needed to bring the document up to date, then
+    /// deserializes it.
+    pub fn open<T: DeserializeOwned>(
+        mut self,
+        migration: &impl SaveMigration,
+    ) -> Result<T, SaveError> {
+        if self.format_version != SAVE_FORMAT_VERSION {
+            return Err(SaveError::UnsupportedFormatVersion(self.format_version));
+        }
+        let supported = migration.schema_version();
+        if self.schema_version > supported {
+            return Err(SaveError::NewerSchemaVersion {
+                found: self.schema_version,
+                supported,
+            });
+        }
+        for from_version in self.schema_version..supported {
+            migration
+                .migrate(from_version, &mut self.document)
+                .map_err(|message| SaveError::Migration {
+                    from_version,
+                    message,
+                })?;
+        }
+        Ok(serde_json::from_value(self.document)?)
+    }
+
+    /// Serializes the envelope as a JSON string.
+    pub fn to_json(&self) -> Result<String, SaveError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parses an envelope from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    Self: Serialize + DeserializeOwned,
+{
+    /// Saves the editor state in a versioned [`SaveEnvelope`].
+    pub fn save(&self, migration: &impl SaveMigration) -> Result<SaveEnvelope, SaveError> {
+        SaveEnvelope::new(self, migration)
+    }
+
+    /// Loads an editor state saved with [`Self::save`], migrating it if it
+    /// was written with an older schema version. Consider calling
+    /// [`Self::repair`] on the result, in case the file was edited by hand.
+    pub fn load(envelope: SaveEnvelope, migration: &impl SaveMigration) -> Result<Self, SaveError> {
+        envelope.open(migration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Version 0 stored `{"a": n}`, version 1 renamed it to `b` and version 2
+    /// to `c`, doubling the value.
+    struct Renames;
+
+    impl SaveMigration for Renames {
+        fn schema_version(&self) -> u32 {
+            2
+        }
+
+        fn migrate(
+            &self,
+            from_version: u32,
+            document: &mut serde_json::Value,
+        ) -> Result<(), String> {
+            let object = document.as_object_mut().ok_or("not an object")?;
+            let (from, to) = match from_version {
+                0 => ("a", "b"),
+                1 => ("b", "c"),
+                _ => unreachable!(),
+            };
+            let value = object.remove(from).ok_or(format!("missing `{from}`"))?;
+            let value = match from_version {
+                1 => json!(value.as_u64().ok_or("not a number")? * 2),
+                _ => value,
+            };
+            object.insert(to.to_owned(), value);
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Current {
+        c: u64,
+    }
+
+    fn envelope(schema_version: u32, document: serde_json::Value) -> SaveEnvelope {
+        SaveEnvelope {
+            format_version: SAVE_FORMAT_VERSION,
+            library_version: "0.0.0".into(),
+            schema_version,
+            document,
+        }
+    }
+
+    #[test]
+    fn migrations_are_chained() {
+        let opened: Current = envelope(0, json!({ "a": 21 })).open(&Renames).unwrap();
+        assert_eq!(opened, Current { c: 42 });
+        let opened: Current = envelope(1, json!({ "b": 1 })).open(&Renames).unwrap();
+        assert_eq!(opened, Current { c: 2 });
+
+        let saved = SaveEnvelope::new(&Current { c: 3 }, &Renames).unwrap();
+        assert_eq!(saved.schema_version, 2);
+        assert_eq!(saved.open::<Current>(&Renames).unwrap(), Current { c: 3 });
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        assert!(matches!(
+            envelope(3, json!({ "c": 1 })).open::<Current>(&Renames),
+            Err(SaveError::NewerSchemaVersion {
+                found: 3,
+                supported: 2
+            })
+        ));
+
+        let mut future = envelope(2, json!({ "c": 1 }));
+        future.format_version = SAVE_FORMAT_VERSION + 1;
+        assert!(matches!(
+            future.open::<Current>(&Renames),
+            Err(SaveError::UnsupportedFormatVersion(version)) if version == SAVE_FORMAT_VERSION + 1
+        ));
+
+        assert!(matches!(
+            envelope(0, json!({ "b": 1 })).open::<Current>(&Renames),
+            Err(SaveError::Migration {
+                from_version: 0,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_envelopes() {
+        assert!(matches!(
+            SaveEnvelope::from_json("{\"format_version\": 1}"),
+            Err(SaveError::Serde(_))
+        ));
+        assert!(matches!(
+            SaveEnvelope::from_json("not json"),
+            Err(SaveError::Serde(_))
+        ));
+        assert!(matches!(
+            envelope(2, json!({ "c": "text" })).open::<Current>(&Renames),
+            Err(SaveError::Serde(_))
+        ));
+    }
+
+    #[test]
+    fn editor_state_round_trip() {
+        type TestState = GraphEditorState<(), String, f32, (), ()>;
+        let mut state = TestState::new(1.0);
+        let node_id = state.graph.add_node("Node".into(), (), |graph, node_id| {
+            graph.add_output_param(node_id, "out".into(), "scalar".into());
+        });
+        state.node_positions.insert(node_id, egui::pos2(1.0, 2.0));
+        state.node_order.push(node_id);
+
+        let json = state.save(&()).unwrap().to_json().unwrap();
+        let loaded = TestState::load(SaveEnvelope::from_json(&json).unwrap(), &()).unwrap();
+        assert_eq!(loaded.graph[node_id].label, "Node");
+        assert_eq!(loaded.node_positions[node_id], egui::pos2(1.0, 2.0));
+    }
+
+    #[test]
+    fn ongoing_gestures_are_not_saved() {
+        type TestState = GraphEditorState<(), String, f32, (), ()>;
+        let mut state = TestState::new(1.0);
+        let node_id = state.graph.add_node("Node".into(), (), |graph, node_id| {
+            graph.add_input_param(
+                node_id,
+                "in".into(),
+                "scalar".into(),
+                0.0,
+                InputParamKind::ConnectionOnly,
+                true,
+            );
+            graph.add_output_param(node_id, "out".into(), "scalar".into());
+        });
+        state.node_positions.insert(node_id, egui::pos2(1.0, 2.0));
+        state.node_order.push(node_id);
+        let node = &state.graph[node_id];
+        let (input, output) = (
+            node.get_input("in").unwrap(),
+            node.get_output("out").unwrap(),
+        );
+        state.selected_connections.push((input, output));
+        state.ongoing_node_drag = Some(node_id);
+        state.ongoing_knife_cut = Some(vec![egui::pos2(0.0, 0.0)]);
+        state.context_menu = Some(ContextMenu {
+            target: ContextMenuTarget::Node(node_id),
+            position: egui::pos2(0.0, 0.0),
+        });
+
+        let json = state.save(&()).unwrap().to_json().unwrap();
+        let loaded = TestState::load(SaveEnvelope::from_json(&json).unwrap(), &()).unwrap();
+        assert_eq!(loaded.ongoing_node_drag, None);
+        assert_eq!(loaded.ongoing_knife_cut, None);
+        assert!(loaded.context_menu.is_none());
+        assert!(loaded.selected_connections.is_empty());
+    }
+}
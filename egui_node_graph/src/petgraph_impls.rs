@@ -0,0 +1,564 @@
+use super::*;
+use std::collections::{HashMap, HashSet};
+
+use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::visit;
+use petgraph::Direction;
+use slotmap::Key;
+
+/// A connection of a [`Graph`], seen as an edge from the node of its output to
+/// the node of its input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConnectionRef {
+    pub output: OutputId,
+    pub input: InputId,
+    /// The node of `output`.
+    pub source: NodeId,
+    /// The node of `input`.
+    pub target: NodeId,
+}
+
+impl visit::EdgeRef for ConnectionRef {
+    type NodeId = NodeId;
+    type EdgeId = (OutputId, InputId);
+    type Weight = ();
+
+    fn source(&self) -> NodeId {
+        self.source
+    }
+
+    fn target(&self) -> NodeId {
+        self.target
+    }
+
+    fn weight(&self) -> &() {
+        &()
+    }
+
+    fn id(&self) -> (OutputId, InputId) {
+        (self.output, self.input)
+    }
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Copies the structure of the graph into a petgraph [`StableDiGraph`].
+    /// Node weights are the ids of the nodes, and there is an edge carrying
+    /// `(output, input)` for every connection. Also returns the index of each
+    /// node in the new graph.
+    pub fn to_petgraph(
+        &self,
+    ) -> (
+        StableDiGraph<NodeId, (OutputId, InputId)>,
+        HashMap<NodeId, NodeIndex>,
+    ) {
+        let mut petgraph = StableDiGraph::with_capacity(self.nodes.len(), self.connections.len());
+        let indices: HashMap<_, _> = self
+            .iter_nodes()
+            .map(|node_id| (node_id, petgraph.add_node(node_id)))
+            .collect();
+        for connection in self.connection_refs() {
+            petgraph.add_edge(
+                indices[&connection.source],
+                indices[&connection.target],
+                (connection.output, connection.input),
+            );
+        }
+        (petgraph, indices)
+    }
+
+    /// All the connections of the graph, skipping dangling ones: Those whose
+    /// params or nodes no longer exist.
+    fn connection_refs(&self) -> impl Iterator<Item = ConnectionRef> + '_ {
+        self.iter_connections().filter_map(|(input, output)| {
+            let connection = ConnectionRef {
+                output,
+                input,
+                source: self.try_get_output(output)?.node,
+                target: self.try_get_input(input)?.node,
+            };
+            (self.nodes.contains_key(connection.source)
+                && self.nodes.contains_key(connection.target))
+            .then_some(connection)
+        })
+    }
+
+    fn connections_directed(&self, node_id: NodeId, direction: Direction) -> Vec<ConnectionRef> {
+        self.connection_refs()
+            .filter(|connection| match direction {
+                Direction::Outgoing => connection.source == node_id,
+                Direction::Incoming => connection.target == node_id,
+            })
+            .collect()
+    }
+
+    /// A view of the graph implementing the petgraph visit traits faster than
+    /// the graph itself. See [`GraphAdjacency`].
+    pub fn adjacency(&self) -> GraphAdjacency<'_, NodeData, DataType, ValueType> {
+        GraphAdjacency::new(self)
+    }
+}
+
+/// The index of a node in the slotmap, which stays the same as long as the
+/// node exists.
+fn slot_index(node_id: NodeId) -> usize {
+    (node_id.data().as_ffi() & 0xffff_ffff) as usize
+}
+
+impl<NodeData, DataType, ValueType> visit::GraphBase for Graph<NodeData, DataType, ValueType> {
+    type NodeId = NodeId;
+    type EdgeId = (OutputId, InputId);
+}
+
+impl<NodeData, DataType, ValueType> visit::Data for Graph<NodeData, DataType, ValueType> {
+    type NodeWeight = Node<NodeData>;
+    type EdgeWeight = ();
+}
+
+impl<NodeData, DataType, ValueType> visit::GraphProp for Graph<NodeData, DataType, ValueType> {
+    type EdgeType = petgraph::Directed;
+}
+
+impl<NodeData, DataType, ValueType> visit::NodeCount for Graph<NodeData, DataType, ValueType> {
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::EdgeCount for Graph<NodeData, DataType, ValueType> {
+    fn edge_count(&self) -> usize {
+        self.connection_refs().count()
+    }
+}
+
+/// Nodes are indexed by their slot in the slotmap, so indices have gaps where
+/// nodes were removed, and `from_index` scans the nodes.
+impl<NodeData, DataType, ValueType> visit::NodeIndexable for Graph<NodeData, DataType, ValueType> {
+    fn node_bound(&self) -> usize {
+        self.iter_nodes()
+            .map(|node_id| slot_index(node_id) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn to_index(&self, node_id: NodeId) -> usize {
+        slot_index(node_id)
+    }
+
+    fn from_index(&self, index: usize) -> NodeId {
+        self.iter_nodes()
+            .find(|node_id| slot_index(*node_id) == index)
+            .expect("The index should belong to a node of the graph")
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::Visitable for Graph<NodeData, DataType, ValueType> {
+    type Map = HashSet<NodeId>;
+
+    fn visit_map(&self) -> HashSet<NodeId> {
+        HashSet::with_capacity(self.nodes.len())
+    }
+
+    fn reset_map(&self, map: &mut HashSet<NodeId>) {
+        map.clear();
+    }
+}
+
+impl<'a, NodeData, DataType, ValueType> visit::IntoNodeIdentifiers
+    for &'a Graph<NodeData, DataType, ValueType>
+{
+    type NodeIdentifiers = slotmap::basic::Keys<'a, NodeId, Node<NodeData>>;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.nodes.keys()
+    }
+}
+
+impl<'a, NodeData, DataType, ValueType> visit::IntoNodeReferences
+    for &'a Graph<NodeData, DataType, ValueType>
+{
+    type NodeRef = (NodeId, &'a Node<NodeData>);
+    type NodeReferences = slotmap::basic::Iter<'a, NodeId, Node<NodeData>>;
+
+    fn node_references(self) -> Self::NodeReferences {
+        self.nodes.iter()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::IntoNeighbors for &Graph<NodeData, DataType, ValueType> {
+    type Neighbors = std::vec::IntoIter<NodeId>;
+
+    fn neighbors(self, node_id: NodeId) -> Self::Neighbors {
+        visit::IntoNeighborsDirected::neighbors_directed(self, node_id, Direction::Outgoing)
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::IntoNeighborsDirected
+    for &Graph<NodeData, DataType, ValueType>
+{
+    type NeighborsDirected = std::vec::IntoIter<NodeId>;
+
+    fn neighbors_directed(self, node_id: NodeId, direction: Direction) -> Self::NeighborsDirected {
+        self.connections_directed(node_id, direction)
+            .into_iter()
+            .map(|connection| match direction {
+                Direction::Outgoing => connection.target,
+                Direction::Incoming => connection.source,
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::IntoEdgeReferences
+    for &Graph<NodeData, DataType, ValueType>
+{
+    type EdgeRef = ConnectionRef;
+    type EdgeReferences = std::vec::IntoIter<ConnectionRef>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        self.connection_refs().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::IntoEdges for &Graph<NodeData, DataType, ValueType> {
+    type Edges = std::vec::IntoIter<ConnectionRef>;
+
+    fn edges(self, node_id: NodeId) -> Self::Edges {
+        self.connections_directed(node_id, Direction::Outgoing)
+            .into_iter()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::IntoEdgesDirected
+    for &Graph<NodeData, DataType, ValueType>
+{
+    type EdgesDirected = std::vec::IntoIter<ConnectionRef>;
+
+    fn edges_directed(self, node_id: NodeId, direction: Direction) -> Self::EdgesDirected {
+        self.connections_directed(node_id, direction).into_iter()
+    }
+}
+
+/// A view of a [`Graph`] implementing the petgraph visit traits, like the
+/// graph itself, for algorithms on large graphs.
+///
+/// The graph finds the connections of a node by scanning all of them. The
+/// view indexes all the nodes and connections once instead, so the queries
+/// made by the algorithms don't need to scan the whole graph. It borrows the
+/// graph, so it must be built again after the graph changes.
+pub struct GraphAdjacency<'a, NodeData, DataType, ValueType> {
+    pub graph: &'a Graph<NodeData, DataType, ValueType>,
+    /// The nodes, by compact index.
+    nodes: Vec<NodeId>,
+    indices: SecondaryMap<NodeId, usize>,
+    edges: Vec<ConnectionRef>,
+    outgoing: SecondaryMap<NodeId, Vec<ConnectionRef>>,
+    incoming: SecondaryMap<NodeId, Vec<ConnectionRef>>,
+}
+
+impl<'a, NodeData, DataType, ValueType> GraphAdjacency<'a, NodeData, DataType, ValueType> {
+    pub fn new(graph: &'a Graph<NodeData, DataType, ValueType>) -> Self {
+        let nodes: Vec<NodeId> = graph.iter_nodes().collect();
+        let mut indices = SecondaryMap::new();
+        let mut outgoing = SecondaryMap::new();
+        let mut incoming = SecondaryMap::new();
+        for (index, node_id) in nodes.iter().copied().enumerate() {
+            indices.insert(node_id, index);
+            outgoing.insert(node_id, Vec::new());
+            incoming.insert(node_id, Vec::new());
+        }
+        let edges: Vec<ConnectionRef> = graph.connection_refs().collect();
+        for connection in &edges {
+            outgoing[connection.source].push(*connection);
+            incoming[connection.target].push(*connection);
+        }
+        Self {
+            graph,
+            nodes,
+            indices,
+            edges,
+            outgoing,
+            incoming,
+        }
+    }
+
+    fn connections_directed(&self, node_id: NodeId, direction: Direction) -> &[ConnectionRef] {
+        let connections = match direction {
+            Direction::Outgoing => &self.outgoing,
+            Direction::Incoming => &self.incoming,
+        };
+        connections.get(node_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::GraphBase
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type NodeId = NodeId;
+    type EdgeId = (OutputId, InputId);
+}
+
+impl<NodeData, DataType, ValueType> visit::Data
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type NodeWeight = Node<NodeData>;
+    type EdgeWeight = ();
+}
+
+impl<NodeData, DataType, ValueType> visit::GraphProp
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type EdgeType = petgraph::Directed;
+}
+
+impl<NodeData, DataType, ValueType> visit::NodeCount
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::EdgeCount
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::NodeIndexable
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    fn node_bound(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn to_index(&self, node_id: NodeId) -> usize {
+        self.indices[node_id]
+    }
+
+    fn from_index(&self, index: usize) -> NodeId {
+        self.nodes[index]
+    }
+}
+
+impl<NodeData, DataType, ValueType> visit::NodeCompactIndexable
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+}
+
+impl<NodeData, DataType, ValueType> visit::Visitable
+    for GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type Map = HashSet<NodeId>;
+
+    fn visit_map(&self) -> HashSet<NodeId> {
+        HashSet::with_capacity(self.nodes.len())
+    }
+
+    fn reset_map(&self, map: &mut HashSet<NodeId>) {
+        map.clear();
+    }
+}
+
+impl<'b, NodeData, DataType, ValueType> visit::IntoNodeIdentifiers
+    for &'b GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type NodeIdentifiers = std::iter::Copied<std::slice::Iter<'b, NodeId>>;
+
+    fn node_identifiers(self) -> Self::NodeIdentifiers {
+        self.nodes.iter().copied()
+    }
+}
+
+impl<'a, NodeData, DataType, ValueType> visit::IntoNodeReferences
+    for &GraphAdjacency<'a, NodeData, DataType, ValueType>
+{
+    type NodeRef = (NodeId, &'a Node<NodeData>);
+    type NodeReferences = slotmap::basic::Iter<'a, NodeId, Node<NodeData>>;
+
+    fn node_references(self) -> Self::NodeReferences {
+        self.graph.nodes.iter()
+    }
+}
+
+/// The neighbors of a node in a [`GraphAdjacency`].
+pub type Neighbors<'b> =
+    std::iter::Map<std::slice::Iter<'b, ConnectionRef>, fn(&ConnectionRef) -> NodeId>;
+
+impl<'b, NodeData, DataType, ValueType> visit::IntoNeighbors
+    for &'b GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type Neighbors = Neighbors<'b>;
+
+    fn neighbors(self, node_id: NodeId) -> Self::Neighbors {
+        visit::IntoNeighborsDirected::neighbors_directed(self, node_id, Direction::Outgoing)
+    }
+}
+
+impl<'b, NodeData, DataType, ValueType> visit::IntoNeighborsDirected
+    for &'b GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type NeighborsDirected = Neighbors<'b>;
+
+    fn neighbors_directed(self, node_id: NodeId, direction: Direction) -> Self::NeighborsDirected {
+        let other_end: fn(&ConnectionRef) -> NodeId = match direction {
+            Direction::Outgoing => |connection| connection.target,
+            Direction::Incoming => |connection| connection.source,
+        };
+        self.connections_directed(node_id, direction)
+            .iter()
+            .map(other_end)
+    }
+}
+
+impl<'b, NodeData, DataType, ValueType> visit::IntoEdgeReferences
+    for &'b GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type EdgeRef = ConnectionRef;
+    type EdgeReferences = std::iter::Copied<std::slice::Iter<'b, ConnectionRef>>;
+
+    fn edge_references(self) -> Self::EdgeReferences {
+        self.edges.iter().copied()
+    }
+}
+
+impl<'b, NodeData, DataType, ValueType> visit::IntoEdges
+    for &'b GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type Edges = std::iter::Copied<std::slice::Iter<'b, ConnectionRef>>;
+
+    fn edges(self, node_id: NodeId) -> Self::Edges {
+        visit::IntoEdgesDirected::edges_directed(self, node_id, Direction::Outgoing)
+    }
+}
+
+impl<'b, NodeData, DataType, ValueType> visit::IntoEdgesDirected
+    for &'b GraphAdjacency<'_, NodeData, DataType, ValueType>
+{
+    type EdgesDirected = std::iter::Copied<std::slice::Iter<'b, ConnectionRef>>;
+
+    fn edges_directed(self, node_id: NodeId, direction: Direction) -> Self::EdgesDirected {
+        self.connections_directed(node_id, direction)
+            .iter()
+            .copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `a -> b -> c -> b`, plus an unconnected node `d`.
+    fn graph() -> (Graph<(), (), ()>, [NodeId; 4]) {
+        let mut graph = Graph::new();
+        let mut add_node = || {
+            graph.add_node("node".into(), (), |graph, node_id| {
+                for name in ["x", "y"] {
+                    graph.add_input_param(
+                        node_id,
+                        name.into(),
+                        (),
+                        (),
+                        InputParamKind::ConnectionOnly,
+                        true,
+                    );
+                }
+                graph.add_output_param(node_id, "out".into(), ());
+            })
+        };
+        let nodes = [add_node(), add_node(), add_node(), add_node()];
+        let [a, b, c, _] = nodes;
+        for (from, to, name) in [(a, b, "x"), (b, c, "x"), (c, b, "y")] {
+            let output = graph[from].get_output("out").unwrap();
+            let input = graph[to].get_input(name).unwrap();
+            graph.add_connection(output, input);
+        }
+        (graph, nodes)
+    }
+
+    #[test]
+    fn to_petgraph_keeps_nodes_and_connections() {
+        let (graph, [a, b, ..]) = graph();
+        let (petgraph, indices) = graph.to_petgraph();
+
+        assert_eq!(petgraph.node_count(), 4);
+        assert_eq!(petgraph.edge_count(), 3);
+        assert_eq!(petgraph[indices[&a]], a);
+        let edge = petgraph.find_edge(indices[&a], indices[&b]).unwrap();
+        assert_eq!(
+            petgraph[edge],
+            (
+                graph[a].get_output("out").unwrap(),
+                graph[b].get_input("x").unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn petgraph_algorithms_run_on_graph() {
+        let (graph, nodes) = graph();
+        check_algorithms(&graph, nodes);
+        check_algorithms(&graph.adjacency(), nodes);
+    }
+
+    /// Runs a few algorithms on `a -> b -> c -> b` and `d`, as built by
+    /// [`graph`].
+    fn check_algorithms<G>(graph: G, [a, b, c, d]: [NodeId; 4])
+    where
+        G: visit::IntoNeighborsDirected<NodeId = NodeId>
+            + visit::IntoNodeIdentifiers
+            + visit::IntoEdges
+            + visit::NodeIndexable
+            + visit::Visitable,
+    {
+        let mut sccs = petgraph::algo::tarjan_scc(graph);
+        sccs.iter_mut().for_each(|scc| scc.sort());
+        let mut cycle = vec![b, c];
+        cycle.sort();
+        assert!(sccs.contains(&cycle));
+        assert_eq!(sccs.len(), 3);
+
+        let distances = petgraph::algo::dijkstra(graph, a, None, |_| 1);
+        assert_eq!(distances[&c], 2);
+        assert!(!distances.contains_key(&d));
+
+        let dominators = petgraph::algo::dominators::simple_fast(graph, a);
+        assert_eq!(dominators.immediate_dominator(c), Some(b));
+    }
+
+    #[test]
+    fn toposort_orders_acyclic_graphs() {
+        let (mut graph, [a, b, c, d]) = graph();
+        let back_edge = graph[b].get_input("y").unwrap();
+        graph.remove_connection(back_edge);
+
+        for order in [
+            petgraph::algo::toposort(&graph, None).unwrap(),
+            petgraph::algo::toposort(&graph.adjacency(), None).unwrap(),
+        ] {
+            let position = |node| order.iter().position(|n| *n == node).unwrap();
+            assert!(position(a) < position(b) && position(b) < position(c));
+            assert!(order.contains(&d));
+        }
+    }
+
+    #[test]
+    fn skips_connections_to_removed_nodes() {
+        let (mut graph, [a, b, ..]) = graph();
+        // Leaves the params of `a` behind, still connected to `b`.
+        graph.nodes.remove(a);
+
+        let (petgraph, indices) = graph.to_petgraph();
+        assert_eq!(petgraph.node_count(), 3);
+        assert_eq!(petgraph.edge_count(), 2);
+        assert!(!indices.contains_key(&a));
+        assert_eq!(visit::EdgeCount::edge_count(&graph), 2);
+        let adjacency = graph.adjacency();
+        assert_eq!(visit::EdgeCount::edge_count(&adjacency), 2);
+        let b_inputs =
+            visit::IntoNeighborsDirected::neighbors_directed(&adjacency, b, Direction::Incoming);
+        assert_eq!(b_inputs.count(), 1);
+    }
+}
@@ -0,0 +1,277 @@
+use egui::{Color32, Painter, Pos2, Stroke};
+
+use crate::color_hex_utils::*;
+use crate::utils::ColorUtils;
+
+#[cfg(feature = "persistence")]
+use serde::{Deserialize, Serialize};
+
+/// The shape drawn for a port, see
+/// [`DataTypeTrait::port_shape`](crate::DataTypeTrait::port_shape).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum PortShape {
+    #[default]
+    Circle,
+    Square,
+    Diamond,
+    /// A triangle pointing in the direction of the data flow.
+    Triangle,
+}
+
+impl PortShape {
+    /// Paints the shape centered at `center`. Hollow shapes are only outlined.
+    pub fn paint(
+        &self,
+        painter: &Painter,
+        center: Pos2,
+        radius: f32,
+        color: Color32,
+        hollow: bool,
+    ) {
+        let (fill, stroke, radius) = if hollow {
+            let stroke = Stroke::new(radius * 0.4, color);
+            (Color32::TRANSPARENT, stroke, radius - stroke.width / 2.0)
+        } else {
+            (color, Stroke::NONE, radius)
+        };
+        let points = match self {
+            PortShape::Circle => {
+                painter.circle(center, radius, fill, stroke);
+                return;
+            }
+            PortShape::Square => {
+                let rect = egui::Rect::from_center_size(center, egui::Vec2::splat(radius * 1.8));
+                painter.rect(rect, 0.0, fill, stroke);
+                return;
+            }
+            PortShape::Diamond | PortShape::Triangle => self.polygon(center, radius),
+        };
+        if hollow {
+            painter.add(egui::Shape::closed_line(points, stroke));
+        } else {
+            painter.add(egui::Shape::convex_polygon(points, fill, stroke));
+        }
+    }
+
+    /// The corners of the shape, empty for circles.
+    pub(crate) fn polygon(&self, center: Pos2, radius: f32) -> Vec<Pos2> {
+        match self {
+            PortShape::Circle => vec![],
+            PortShape::Square => vec![
+                center + egui::vec2(-radius * 0.9, -radius * 0.9),
+                center + egui::vec2(radius * 0.9, -radius * 0.9),
+                center + egui::vec2(radius * 0.9, radius * 0.9),
+                center + egui::vec2(-radius * 0.9, radius * 0.9),
+            ],
+            PortShape::Diamond => vec![
+                center - egui::vec2(0.0, radius * 1.2),
+                center + egui::vec2(radius * 1.2, 0.0),
+                center + egui::vec2(0.0, radius * 1.2),
+                center - egui::vec2(radius * 1.2, 0.0),
+            ],
+            PortShape::Triangle => vec![
+                center + egui::vec2(-radius, -radius * 1.1),
+                center + egui::vec2(radius * 1.1, 0.0),
+                center + egui::vec2(-radius, radius * 1.1),
+            ],
+        }
+    }
+}
+
+/// The path followed by the wires connecting two ports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum WireRouting {
+    /// A cubic bezier curve leaving and entering the ports horizontally.
+    #[default]
+    Bezier,
+    /// A straight line between both ports.
+    Straight,
+    /// Horizontal and vertical segments joined by rounded corners.
+    Orthogonal,
+}
+
+/// Colors and measurements used to draw the graph editor. Set
+/// [`GraphEditorState::style`](crate::GraphEditorState::style) to override the
+/// default style, which follows the dark / light mode of egui.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct GraphStyle {
+    /// Fill color of the node body.
+    pub node_background: Color32,
+    /// Fill color of the node title bar, unless overriden by
+    /// [`NodeDataTrait::titlebar_color`](crate::NodeDataTrait::titlebar_color).
+    pub node_titlebar: Color32,
+    /// Color of the node title and other non-interactive text in nodes.
+    pub node_text: Color32,
+    /// Color of the outline drawn around selected nodes.
+    pub selection_outline: Color32,
+    /// Radius of the shapes drawn for ports.
+    pub port_radius: f32,
+    /// Color of a port when the mouse is close enough to connect to it.
+    pub port_hover: Color32,
+    /// Width of the connection wires.
+    pub wire_width: f32,
+    /// The path followed by wires, unless overriden by
+    /// [`DataTypeTrait::wire_routing`](crate::DataTypeTrait::wire_routing).
+    pub wire_routing: WireRouting,
+    /// How much bezier wires bend. `1.0` is the default curve, `0.0` draws
+    /// straight lines.
+    pub wire_curvature: f32,
+    /// Radius of the corners of orthogonal wires.
+    pub wire_corner_radius: f32,
+    /// Length of the dashes of dashed wires, see
+    /// [`DataTypeTrait::wire_dashed`](crate::DataTypeTrait::wire_dashed).
+    pub wire_dash_length: f32,
+    /// Length of the gaps between the dashes of dashed wires.
+    pub wire_gap_length: f32,
+    /// Color of the halo drawn below hovered and selected wires. Its alpha is
+    /// chosen by the editor depending on the kind of highlight.
+    pub wire_highlight: Color32,
+    /// Color of the close button. Also used for the other buttons in the
+    /// title bar and the resize grip.
+    pub close_button: Color32,
+    /// Color of the close button while hovered.
+    pub close_button_hovered: Color32,
+    /// Color of the close button while clicked.
+    pub close_button_clicked: Color32,
+    /// Default speed of the data flow dots, in points per second. See
+    /// [`GraphEditorState::show_data_flow`](crate::GraphEditorState::show_data_flow).
+    pub flow_speed: f32,
+    /// Distance between two consecutive data flow dots.
+    pub flow_dot_spacing: f32,
+    /// Radius of the data flow dots.
+    pub flow_dot_radius: f32,
+    /// Color of the data flow dots.
+    pub flow_dot_color: Color32,
+    /// Drawn over the nodes that are dimmed by
+    /// [`GraphEditorState::dependency_highlight`](crate::GraphEditorState::dependency_highlight).
+    pub dimmed_node_overlay: Color32,
+    /// Opacity multiplier of dimmed wires.
+    pub dimmed_wire_opacity: f32,
+    /// Color of the badge and outline of nodes with an error status, see
+    /// [`NodeDataTrait::status`](crate::NodeDataTrait::status).
+    pub status_error: Color32,
+    /// Color of the badge and outline of nodes with a warning status.
+    pub status_warning: Color32,
+    /// Color of the badge and outline of nodes with a running status.
+    pub status_running: Color32,
+    /// Color of the badge and outline of nodes with a cached status.
+    pub status_cached: Color32,
+    /// Fill color of the box selection rectangle.
+    pub box_selection_fill: Color32,
+    /// Outline color of the box selection rectangle.
+    pub box_selection_stroke: Color32,
+    /// Color of the path drawn by the knife gesture.
+    pub knife: Color32,
+    /// Background color of the node finder.
+    pub finder_background: Color32,
+    /// Text color of the node finder.
+    pub finder_text: Color32,
+}
+
+impl GraphStyle {
+    /// The default style for egui's dark mode.
+    pub fn dark() -> Self {
+        let node_background = color_from_hex("#3f3f3f").unwrap();
+        Self {
+            node_background,
+            node_titlebar: node_background.lighten(0.8),
+            node_text: color_from_hex("#fefefe").unwrap(),
+            selection_outline: Color32::WHITE.lighten(0.8),
+            port_radius: 5.0,
+            port_hover: Color32::WHITE,
+            wire_width: 5.0,
+            wire_routing: WireRouting::Bezier,
+            wire_curvature: 1.0,
+            wire_corner_radius: 8.0,
+            wire_dash_length: 10.0,
+            wire_gap_length: 6.0,
+            wire_highlight: Color32::WHITE,
+            close_button: color_from_hex("#aaaaaa").unwrap(),
+            close_button_hovered: color_from_hex("#dddddd").unwrap(),
+            close_button_clicked: color_from_hex("#ffffff").unwrap(),
+            flow_speed: 40.0,
+            flow_dot_spacing: 20.0,
+            flow_dot_radius: 2.0,
+            flow_dot_color: Color32::WHITE,
+            dimmed_node_overlay: Color32::from_black_alpha(160),
+            dimmed_wire_opacity: 0.2,
+            status_error: Color32::from_rgb(220, 60, 60),
+            status_warning: Color32::from_rgb(230, 170, 40),
+            status_running: Color32::from_rgb(60, 140, 230),
+            status_cached: Color32::from_rgb(80, 180, 90),
+            box_selection_fill: Color32::from_rgba_unmultiplied(200, 200, 200, 20),
+            box_selection_stroke: Color32::from_rgba_unmultiplied(200, 200, 200, 180),
+            knife: Color32::from_rgb(220, 80, 80),
+            finder_background: color_from_hex("#3f3f3f").unwrap(),
+            finder_text: color_from_hex("#fefefe").unwrap(),
+        }
+    }
+
+    /// The default style for egui's light mode.
+    pub fn light() -> Self {
+        let node_background = color_from_hex("#ffffff").unwrap();
+        Self {
+            node_background,
+            node_titlebar: node_background.lighten(0.8),
+            node_text: color_from_hex("#505050").unwrap(),
+            selection_outline: Color32::WHITE.lighten(0.8),
+            port_radius: 5.0,
+            port_hover: Color32::WHITE,
+            wire_width: 5.0,
+            wire_routing: WireRouting::Bezier,
+            wire_curvature: 1.0,
+            wire_corner_radius: 8.0,
+            wire_dash_length: 10.0,
+            wire_gap_length: 6.0,
+            wire_highlight: Color32::WHITE,
+            close_button: color_from_hex("#555555").unwrap(),
+            close_button_hovered: color_from_hex("#222222").unwrap(),
+            close_button_clicked: color_from_hex("#000000").unwrap(),
+            flow_speed: 40.0,
+            flow_dot_spacing: 20.0,
+            flow_dot_radius: 2.0,
+            flow_dot_color: Color32::WHITE,
+            dimmed_node_overlay: Color32::from_white_alpha(160),
+            dimmed_wire_opacity: 0.2,
+            status_error: Color32::from_rgb(220, 60, 60),
+            status_warning: Color32::from_rgb(230, 170, 40),
+            status_running: Color32::from_rgb(60, 140, 230),
+            status_cached: Color32::from_rgb(80, 180, 90),
+            box_selection_fill: Color32::from_rgba_unmultiplied(200, 200, 200, 20),
+            box_selection_stroke: Color32::from_rgba_unmultiplied(200, 200, 200, 180),
+            knife: Color32::from_rgb(220, 80, 80),
+            finder_background: color_from_hex("#fefefe").unwrap(),
+            finder_text: color_from_hex("#3f3f3f").unwrap(),
+        }
+    }
+
+    /// Picks the dark or light preset, depending on the given egui visuals.
+    pub fn from_visuals(visuals: &egui::Visuals) -> Self {
+        if visuals.dark_mode {
+            Self::dark()
+        } else {
+            Self::light()
+        }
+    }
+}
+
+impl GraphStyle {
+    /// The color used for nodes with the given status kind.
+    pub fn status_color(&self, kind: crate::NodeStatusKind) -> Color32 {
+        match kind {
+            crate::NodeStatusKind::Error => self.status_error,
+            crate::NodeStatusKind::Warning => self.status_warning,
+            crate::NodeStatusKind::Running => self.status_running,
+            crate::NodeStatusKind::Cached => self.status_cached,
+        }
+    }
+}
+
+impl Default for GraphStyle {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
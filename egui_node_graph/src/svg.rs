@@ -0,0 +1,406 @@
+use super::*;
+use crate::editor_ui::{collapsed_ports, connection_bezier, connection_polyline};
+use egui::{pos2, vec2, Color32, Pos2, Rect, Stroke};
+use std::fmt::Write;
+
+/// Space left around the nodes in the exported image.
+const SVG_MARGIN: f32 = 20.0;
+/// Horizontal space between the sides of a node and its contents.
+const NODE_PADDING: f32 = 15.0;
+/// Rounding of the node corners, as drawn by the editor.
+const NODE_ROUNDING: f32 = 4.0;
+/// Estimated metrics of a node, for nodes that haven't been drawn.
+const ESTIMATED_TITLEBAR_HEIGHT: f32 = 30.0;
+const ESTIMATED_ROW_HEIGHT: f32 = 24.0;
+const ESTIMATED_NODE_WIDTH: f32 = 200.0;
+
+/// Writes `color` as an SVG paint attribute, e.g. `fill`, with its opacity.
+fn paint(attribute: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let mut paint = format!("{attribute}=\"#{r:02x}{g:02x}{b:02x}\"");
+    if a < 255 {
+        write!(paint, " {attribute}-opacity=\"{:.3}\"", a as f32 / 255.0).unwrap();
+    }
+    paint
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn svg_points(points: &[Pos2]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", p.x, p.y))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl GraphLayout {
+    /// Estimates the layout of the nodes without drawing them: Nodes keep the
+    /// size chosen by the user, or a default width, and have one row per
+    /// parameter. Use [`GraphEditorState::layout`] instead when the editor has
+    /// been drawn, since it matches the actual widgets.
+    pub fn estimate<NodeData, DataType, ValueType, NodeTemplate, UserState>(
+        state: &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    ) -> Self {
+        let mut layout = Self::default();
+        for node_id in state.graph.iter_nodes() {
+            layout.estimate_node(state, node_id);
+        }
+        layout
+    }
+
+    /// Adds the estimated layout of a single node, see [`Self::estimate`].
+    pub(crate) fn estimate_node<NodeData, DataType, ValueType, NodeTemplate, UserState>(
+        &mut self,
+        state: &GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+        node_id: NodeId,
+    ) {
+        let node = &state.graph[node_id];
+        let position = state
+            .node_positions
+            .get(node_id)
+            .copied()
+            .unwrap_or(Pos2::ZERO);
+        let collapsed = state.collapsed_nodes.contains(&node_id);
+        let rows = if collapsed {
+            0
+        } else {
+            node.inputs.len() + node.outputs.len()
+        };
+
+        let mut rect = Rect::from_min_size(
+            position,
+            vec2(
+                ESTIMATED_NODE_WIDTH,
+                ESTIMATED_TITLEBAR_HEIGHT + rows as f32 * ESTIMATED_ROW_HEIGHT,
+            ),
+        );
+        if let Some(size) = state.node_sizes.get(node_id) {
+            rect.set_width(size.x);
+            rect.set_height(rect.height().max(size.y));
+        }
+
+        if collapsed {
+            self.port_locations
+                .extend(collapsed_ports(&state.graph, node_id, rect));
+        } else {
+            let params = node
+                .input_ids()
+                .map(AnyParameterId::Input)
+                .chain(node.output_ids().map(AnyParameterId::Output));
+            for (row, param) in params.enumerate() {
+                let y = rect.top()
+                    + ESTIMATED_TITLEBAR_HEIGHT
+                    + (row as f32 + 0.5) * ESTIMATED_ROW_HEIGHT;
+                let x = match param {
+                    AnyParameterId::Input(_) => rect.left(),
+                    AnyParameterId::Output(_) => rect.right(),
+                };
+                self.port_locations.insert(param, pos2(x, y));
+            }
+        }
+        self.node_rects.insert(node_id, rect);
+        self.titlebar_heights.insert(
+            node_id,
+            if collapsed {
+                rect.height()
+            } else {
+                ESTIMATED_TITLEBAR_HEIGHT
+            },
+        );
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    DataType: DataTypeTrait<UserState>,
+{
+    /// Renders the graph as an SVG image: The node boxes with their titles
+    /// and parameter names, the ports and the connections, colored like in
+    /// the editor. Value widgets and custom node UI are not rendered.
+    ///
+    /// `layout` is usually [`GraphEditorState::layout`], once the editor has
+    /// been drawn. Nodes missing from it are placed with
+    /// [`GraphLayout::estimate`].
+    pub fn to_svg(&self, layout: &GraphLayout, user_state: &mut UserState) -> String {
+        let style = self.style.clone().unwrap_or_default();
+        let estimated = GraphLayout::estimate(self);
+        let nodes = self.ordered_nodes();
+
+        let node_rect = |node_id: NodeId| {
+            (layout.node_rects.get(&node_id))
+                .or_else(|| estimated.node_rects.get(&node_id))
+                .copied()
+                .unwrap_or(Rect::NOTHING)
+        };
+        let bounds = nodes
+            .iter()
+            .map(|node_id| node_rect(*node_id))
+            .fold(Rect::NOTHING, |bounds, rect| bounds.union(rect));
+        if !bounds.is_positive() {
+            return "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\"/>\n"
+                .to_owned();
+        }
+        // Moves the graph so the image starts at the origin.
+        let offset = vec2(SVG_MARGIN, SVG_MARGIN) - bounds.min.to_vec2();
+        let port_location = |param: AnyParameterId| {
+            (layout.port_locations.get(&param))
+                .or_else(|| estimated.port_locations.get(&param))
+                .map(|pos| *pos + offset)
+        };
+
+        let size = bounds.size() + vec2(2.0 * SVG_MARGIN, 2.0 * SVG_MARGIN);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">",
+            w = size.x.ceil(),
+            h = size.y.ceil()
+        )
+        .unwrap();
+
+        /* Connections */
+        for (input, output) in self.graph.iter_connections() {
+            let (src_pos, dst_pos) =
+                match (port_location(output.into()), port_location(input.into())) {
+                    (Some(src_pos), Some(dst_pos)) => (src_pos, dst_pos),
+                    _ => continue,
+                };
+            let typ = &self.graph[output].typ;
+            let routing = typ.wire_routing(user_state).unwrap_or(style.wire_routing);
+            let path = if routing == WireRouting::Bezier {
+                let [a, b, c, d] = connection_bezier(src_pos, dst_pos, Stroke::NONE, &style).points;
+                format!("M {},{} C {}", a.x, a.y, svg_points(&[b, c, d]))
+            } else {
+                let points = connection_polyline(src_pos, dst_pos, routing, &style);
+                format!("M {}", svg_points(&points))
+            };
+            let dash = if typ.wire_dashed(user_state) {
+                format!(
+                    " stroke-dasharray=\"{} {}\"",
+                    style.wire_dash_length, style.wire_gap_length
+                )
+            } else {
+                String::new()
+            };
+            writeln!(
+                svg,
+                "  <path d=\"{path}\" fill=\"none\" {} stroke-width=\"{}\"{dash}/>",
+                paint("stroke", typ.data_type_color(user_state)),
+                style.wire_width,
+            )
+            .unwrap();
+        }
+
+        /* Nodes */
+        for node_id in nodes {
+            let node = &self.graph[node_id];
+            let rect = node_rect(node_id).translate(offset);
+            let collapsed = self.collapsed_nodes.contains(&node_id);
+            let titlebar_height = (layout.titlebar_heights.get(&node_id))
+                .or_else(|| estimated.titlebar_heights.get(&node_id))
+                .copied()
+                .unwrap_or(ESTIMATED_TITLEBAR_HEIGHT)
+                .min(rect.height());
+
+            writeln!(svg, "  <g>").unwrap();
+            writeln!(
+                svg,
+                "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{NODE_ROUNDING}\" {}/>",
+                rect.left(),
+                rect.top(),
+                rect.width(),
+                rect.height(),
+                paint("fill", style.node_background)
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{titlebar_height}\" rx=\"{NODE_ROUNDING}\" {}/>",
+                rect.left(),
+                rect.top(),
+                rect.width(),
+                paint("fill", style.node_titlebar)
+            )
+            .unwrap();
+            if !collapsed && titlebar_height < rect.height() {
+                // Squares the bottom corners of the title bar.
+                writeln!(
+                    svg,
+                    "    <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{NODE_ROUNDING}\" {}/>",
+                    rect.left(),
+                    rect.top() + titlebar_height - NODE_ROUNDING,
+                    rect.width(),
+                    paint("fill", style.node_titlebar)
+                )
+                .unwrap();
+            }
+            writeln!(
+                svg,
+                "    <text x=\"{}\" y=\"{}\" font-size=\"14\" dominant-baseline=\"middle\" {}>{}</text>",
+                rect.left() + NODE_PADDING,
+                rect.top() + titlebar_height / 2.0,
+                paint("fill", style.node_text),
+                escape_xml(&node.label)
+            )
+            .unwrap();
+
+            let params = (node.inputs.iter())
+                .map(|(name, id)| (name, AnyParameterId::Input(*id)))
+                .chain((node.outputs.iter()).map(|(name, id)| (name, AnyParameterId::Output(*id))));
+            for (name, param) in params {
+                let pos = match port_location(param) {
+                    Some(pos) => pos,
+                    None => continue,
+                };
+                if !collapsed {
+                    let (x, anchor) = match param {
+                        AnyParameterId::Input(_) => (rect.left() + NODE_PADDING, "start"),
+                        AnyParameterId::Output(_) => (rect.right() - NODE_PADDING, "end"),
+                    };
+                    writeln!(
+                        svg,
+                        "    <text x=\"{x}\" y=\"{}\" font-size=\"13\" text-anchor=\"{anchor}\" dominant-baseline=\"middle\" {}>{}</text>",
+                        pos.y,
+                        paint("fill", style.node_text),
+                        escape_xml(name)
+                    )
+                    .unwrap();
+                }
+
+                let typ = self.graph.any_param_type(param).unwrap();
+                let color = typ.data_type_color(user_state);
+                let optional = match param {
+                    AnyParameterId::Input(input) => {
+                        matches!(self.graph[input].kind, InputParamKind::ConnectionOrConstant)
+                    }
+                    AnyParameterId::Output(_) => false,
+                };
+                let hollow = typ.port_hollow(optional, user_state);
+                let (fill, radius) = if hollow {
+                    let width = style.port_radius * 0.4;
+                    (
+                        format!(
+                            "fill=\"none\" {} stroke-width=\"{width}\"",
+                            paint("stroke", color)
+                        ),
+                        style.port_radius - width / 2.0,
+                    )
+                } else {
+                    (paint("fill", color), style.port_radius)
+                };
+                let shape = typ.port_shape(user_state);
+                if shape == PortShape::Circle {
+                    writeln!(
+                        svg,
+                        "    <circle cx=\"{}\" cy=\"{}\" r=\"{radius}\" {fill}/>",
+                        pos.x, pos.y
+                    )
+                    .unwrap();
+                } else {
+                    writeln!(
+                        svg,
+                        "    <polygon points=\"{}\" {fill}/>",
+                        svg_points(&shape.polygon(pos, radius))
+                    )
+                    .unwrap();
+                }
+            }
+            writeln!(svg, "  </g>").unwrap();
+        }
+
+        writeln!(svg, "</svg>").unwrap();
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[derive(PartialEq, Eq)]
+    struct Scalar;
+
+    impl DataTypeTrait<()> for Scalar {
+        fn data_type_color(&self, _user_state: &mut ()) -> Color32 {
+            Color32::from_rgb(0x38, 0xb6, 0xff)
+        }
+
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed("scalar")
+        }
+
+        fn port_hollow(&self, optional: bool, _user_state: &mut ()) -> bool {
+            optional
+        }
+    }
+
+    #[test]
+    fn renders_nodes_ports_and_connections() {
+        let mut state = GraphEditorState::<(), Scalar, (), (), ()>::new(1.0);
+        let mut add_node = |label: &str, pos: Pos2| {
+            let node_id = state.graph.add_node(label.into(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "A".into(),
+                    Scalar,
+                    (),
+                    InputParamKind::ConnectionOnly,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), Scalar);
+            });
+            state.node_positions.insert(node_id, pos);
+            state.node_order.push(node_id);
+            node_id
+        };
+        let a = add_node("Value", pos2(100.0, 100.0));
+        let b = add_node("A & B", pos2(400.0, 100.0));
+        let output = state.graph[a].get_output("out").unwrap();
+        let input = state.graph[b].get_input("A").unwrap();
+        state.graph.add_connection(output, input);
+
+        let svg = state.to_svg(&GraphLayout::default(), &mut ());
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.contains(">A &amp; B</text>"));
+        assert_eq!(svg.matches("<circle ").count(), 4);
+        assert_eq!(svg.matches("<path ").count(), 1);
+        assert!(svg.contains("stroke=\"#38b6ff\""));
+    }
+
+    #[test]
+    fn outlines_hollow_ports() {
+        let mut state = GraphEditorState::<(), Scalar, (), (), ()>::new(1.0);
+        let node_id = state.graph.add_node("Node".into(), (), |graph, node_id| {
+            for (name, kind) in [
+                ("required", InputParamKind::ConnectionOnly),
+                ("optional", InputParamKind::ConnectionOrConstant),
+            ] {
+                graph.add_input_param(node_id, name.into(), Scalar, (), kind, true);
+            }
+        });
+        state.node_order.push(node_id);
+
+        let svg = state.to_svg(&GraphLayout::default(), &mut ());
+
+        assert_eq!(svg.matches("<circle ").count(), 2);
+        assert_eq!(svg.matches("fill=\"none\"").count(), 1);
+    }
+}
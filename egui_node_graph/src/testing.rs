@@ -0,0 +1,587 @@
+use crate::editor_ui::NodeLayoutMemory;
+
+use super::*;
+use egui::*;
+
+/// The frame rate simulated by the [`EditorHarness`].
+const FRAME_TIME: f64 = 1.0 / 60.0;
+
+/// Number of intermediate pointer moves in a scripted drag. egui needs a few
+/// frames of movement before it considers a press to be a drag.
+const DRAG_STEPS: usize = 5;
+
+struct TemplateList<NodeTemplate>(Vec<NodeTemplate>);
+
+impl<NodeTemplate: Clone> NodeTemplateIter for TemplateList<NodeTemplate> {
+    type Item = NodeTemplate;
+
+    fn all_kinds(&self) -> Vec<Self::Item> {
+        self.0.clone()
+    }
+}
+
+/// Draws a [`GraphEditorState`] in a headless egui context, feeding it
+/// scripted pointer and keyboard events. Every action runs one or more frames
+/// and returns the node responses produced during those frames, so tests can
+/// assert on them and on the resulting graph. [`EditorHarness::run_frame`]
+/// returns the whole [`GraphResponse`] of its frame.
+///
+/// Positions are in screen coordinates. Use [`EditorHarness::port_pos`] and
+/// [`EditorHarness::node_title_pos`] to find the elements drawn in the last
+/// frame.
+pub struct EditorHarness<NodeData, DataType, ValueType, NodeTemplate, UserState> {
+    pub ctx: Context,
+    pub state: GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+    pub user_state: UserState,
+    /// The templates offered by the node finder.
+    pub all_kinds: Vec<NodeTemplate>,
+    /// The modifier keys held during the following events.
+    pub modifiers: Modifiers,
+    screen_size: Vec2,
+    editor_rect: Rect,
+    editor_id: Id,
+    pointer_pos: Pos2,
+    time: f64,
+    pending_events: Vec<Event>,
+}
+
+impl<NodeData, DataType, ValueType, NodeTemplate, UserResponse, UserState, CategoryType>
+    EditorHarness<NodeData, DataType, ValueType, NodeTemplate, UserState>
+where
+    NodeData: NodeDataTrait<
+            Response = UserResponse,
+            UserState = UserState,
+            DataType = DataType,
+            ValueType = ValueType,
+        > + Clone,
+    UserResponse: UserResponseTrait,
+    ValueType: WidgetValueTrait<Response = UserResponse, UserState = UserState, NodeData = NodeData>
+        + Clone,
+    NodeTemplate: NodeTemplateTrait<
+        NodeData = NodeData,
+        DataType = DataType,
+        ValueType = ValueType,
+        UserState = UserState,
+        CategoryType = CategoryType,
+    >,
+    DataType: DataTypeTrait<UserState> + Clone,
+    CategoryType: CategoryTrait,
+{
+    /// Creates the harness and draws a first frame, so the layout of the
+    /// nodes is known.
+    pub fn new(
+        state: GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState>,
+        user_state: UserState,
+        all_kinds: Vec<NodeTemplate>,
+    ) -> Self {
+        let mut harness = Self {
+            ctx: Context::default(),
+            state,
+            user_state,
+            all_kinds,
+            modifiers: Modifiers::NONE,
+            screen_size: vec2(1024.0, 768.0),
+            editor_rect: Rect::NOTHING,
+            editor_id: Id::null(),
+            pointer_pos: Pos2::ZERO,
+            time: 0.0,
+            pending_events: Vec::new(),
+        };
+        harness.run_frame();
+        harness
+    }
+
+    /// Draws one frame, processing all the events queued since the last one.
+    pub fn run_frame(&mut self) -> GraphResponse<UserResponse, NodeData> {
+        let raw_input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, self.screen_size)),
+            time: Some(self.time),
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.pending_events),
+            ..Default::default()
+        };
+        self.time += FRAME_TIME;
+
+        let mut response = GraphResponse::default();
+        let state = &mut self.state;
+        let user_state = &mut self.user_state;
+        let all_kinds = TemplateList(self.all_kinds.clone());
+        let mut editor_rect = self.editor_rect;
+        let mut editor_id = self.editor_id;
+        let _ = self.ctx.run(raw_input, |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                editor_rect = ui.max_rect();
+                editor_id = ui.id();
+                response = state.draw_graph_editor(ui, all_kinds, user_state, Vec::new());
+            });
+        });
+        self.editor_rect = editor_rect;
+        self.editor_id = editor_id;
+        response
+    }
+
+    /// Queues an event for the next frame.
+    pub fn push_event(&mut self, event: Event) {
+        self.pending_events.push(event);
+    }
+
+    pub fn pointer_move(&mut self, pos: Pos2) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        self.pointer_pos = pos;
+        self.push_event(Event::PointerMoved(pos));
+        self.run_frame().node_responses
+    }
+
+    pub fn pointer_press(
+        &mut self,
+        button: PointerButton,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        self.push_pointer_button(button, true);
+        self.run_frame().node_responses
+    }
+
+    pub fn pointer_release(
+        &mut self,
+        button: PointerButton,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        self.push_pointer_button(button, false);
+        self.run_frame().node_responses
+    }
+
+    /// Moves the pointer to `pos` and clicks there.
+    pub fn click(
+        &mut self,
+        pos: Pos2,
+        button: PointerButton,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = self.pointer_move(pos);
+        responses.extend(self.pointer_press(button));
+        responses.extend(self.pointer_release(button));
+        responses
+    }
+
+    /// Presses the pointer at `from`, moves it to `to` over a few frames and
+    /// releases it there.
+    pub fn drag(
+        &mut self,
+        from: Pos2,
+        to: Pos2,
+        button: PointerButton,
+    ) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        let mut responses = self.pointer_move(from);
+        responses.extend(self.pointer_press(button));
+        for step in 1..=DRAG_STEPS {
+            let t = step as f32 / DRAG_STEPS as f32;
+            responses.extend(self.pointer_move(from + (to - from) * t));
+        }
+        responses.extend(self.pointer_release(button));
+        responses
+    }
+
+    /// Presses and releases a key.
+    pub fn key(&mut self, key: Key) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        for pressed in [true, false] {
+            self.push_event(Event::Key {
+                key,
+                pressed,
+                repeat: false,
+                modifiers: self.modifiers,
+            });
+        }
+        self.run_frame().node_responses
+    }
+
+    /// Types text into the focused widget, e.g. the node finder search bar.
+    pub fn type_text(&mut self, text: &str) -> Vec<NodeResponse<UserResponse, NodeData>> {
+        self.push_event(Event::Text(text.to_owned()));
+        self.run_frame().node_responses
+    }
+
+    /// The rect of a node in the last frame it was drawn.
+    pub fn node_rect(&self, node_id: NodeId) -> Rect {
+        self.layout(node_id)
+            .rect
+            .translate(self.node_pos(node_id).to_vec2())
+    }
+
+    /// A point in the title bar of a node, which can be used to select or drag
+    /// it.
+    pub fn node_title_pos(&self, node_id: NodeId) -> Pos2 {
+        self.node_rect(node_id).left_top() + vec2(20.0, 10.0)
+    }
+
+    /// The center of a port in the last frame it was drawn.
+    pub fn port_pos(&self, param: impl Into<AnyParameterId>) -> Pos2 {
+        let param = param.into();
+        let node_id = match param {
+            AnyParameterId::Input(input) => self.state.graph[input].node,
+            AnyParameterId::Output(output) => self.state.graph[output].node,
+        };
+        let offset = self
+            .layout(node_id)
+            .ports
+            .iter()
+            .find(|(stored, _)| *stored == param)
+            .map(|(_, offset)| *offset)
+            .unwrap_or_else(|| panic!("The port {param:?} was not drawn"));
+        self.node_pos(node_id) + offset
+    }
+
+    fn node_pos(&self, node_id: NodeId) -> Pos2 {
+        self.state.node_positions[node_id]
+            + self.state.pan_zoom.pan
+            + self.editor_rect.min.to_vec2()
+    }
+
+    fn layout(&self, node_id: NodeId) -> NodeLayoutMemory {
+        NodeLayoutMemory::load(&self.ctx, self.editor_id, node_id)
+            .unwrap_or_else(|| panic!("The node {node_id:?} was not drawn"))
+    }
+
+    fn push_pointer_button(&mut self, button: PointerButton, pressed: bool) {
+        self.push_event(Event::PointerButton {
+            pos: self.pointer_pos,
+            button,
+            pressed,
+            modifiers: self.modifiers,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum TestDataType {
+        Number,
+    }
+
+    impl DataTypeTrait<()> for TestDataType {
+        fn data_type_color(&self, _user_state: &mut ()) -> Color32 {
+            Color32::LIGHT_BLUE
+        }
+
+        fn name(&self) -> Cow<'_, str> {
+            Cow::Borrowed("number")
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Default)]
+    struct TestValue;
+
+    #[derive(Clone, Debug)]
+    struct TestResponse;
+
+    impl UserResponseTrait for TestResponse {}
+
+    impl WidgetValueTrait for TestValue {
+        type Response = TestResponse;
+        type UserState = ();
+        type NodeData = TestNodeData;
+
+        fn value_widget(
+            &mut self,
+            param_name: &str,
+            _node_id: NodeId,
+            ui: &mut Ui,
+            _user_state: &mut (),
+            _node_data: &TestNodeData,
+        ) -> Vec<TestResponse> {
+            ui.label(param_name);
+            Vec::new()
+        }
+    }
+
+    thread_local! {
+        /// The nodes whose UI was drawn, to tell culled nodes apart.
+        static DRAWN_NODES: std::cell::RefCell<Vec<NodeId>> = Default::default();
+    }
+
+    #[derive(Clone, Debug)]
+    struct TestNodeData;
+
+    impl NodeDataTrait for TestNodeData {
+        type Response = TestResponse;
+        type UserState = ();
+        type DataType = TestDataType;
+        type ValueType = TestValue;
+
+        fn bottom_ui(
+            &self,
+            _ui: &mut Ui,
+            node_id: NodeId,
+            _graph: &Graph<Self, TestDataType, TestValue>,
+            _user_state: &mut (),
+        ) -> Vec<NodeResponse<TestResponse, Self>> {
+            DRAWN_NODES.with(|drawn| drawn.borrow_mut().push(node_id));
+            Vec::new()
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct AddTemplate;
+
+    impl NodeTemplateTrait for AddTemplate {
+        type NodeData = TestNodeData;
+        type DataType = TestDataType;
+        type ValueType = TestValue;
+        type UserState = ();
+        type CategoryType = &'static str;
+
+        fn node_finder_label(&self, _user_state: &mut ()) -> Cow<'_, str> {
+            Cow::Borrowed("Add")
+        }
+
+        fn node_graph_label(&self, _user_state: &mut ()) -> String {
+            "Add".into()
+        }
+
+        fn user_data(&self, _user_state: &mut ()) -> TestNodeData {
+            TestNodeData
+        }
+
+        fn build_node(
+            &self,
+            graph: &mut Graph<TestNodeData, TestDataType, TestValue>,
+            _user_state: &mut (),
+            node_id: NodeId,
+        ) {
+            for name in ["A", "B"] {
+                graph.add_input_param(
+                    node_id,
+                    name.into(),
+                    TestDataType::Number,
+                    TestValue,
+                    InputParamKind::ConnectionOrConstant,
+                    true,
+                );
+            }
+            graph.add_output_param(node_id, "out".into(), TestDataType::Number);
+        }
+    }
+
+    type TestHarness = EditorHarness<TestNodeData, TestDataType, TestValue, AddTemplate, ()>;
+
+    /// A harness showing two unconnected "Add" nodes, side by side.
+    fn two_nodes() -> (TestHarness, NodeId, NodeId) {
+        let mut state = GraphEditorState::new(1.0);
+        let mut add_node = |pos: Pos2| {
+            let node_id = state
+                .graph
+                .add_node("Add".into(), TestNodeData, |graph, node_id| {
+                    AddTemplate.build_node(graph, &mut (), node_id)
+                });
+            state.node_positions.insert(node_id, pos);
+            state.node_order.push(node_id);
+            node_id
+        };
+        let a = add_node(pos2(50.0, 50.0));
+        let b = add_node(pos2(400.0, 50.0));
+        (TestHarness::new(state, (), vec![AddTemplate]), a, b)
+    }
+
+    fn output(harness: &TestHarness, node_id: NodeId) -> OutputId {
+        harness.state.graph[node_id].get_output("out").unwrap()
+    }
+
+    fn input(harness: &TestHarness, node_id: NodeId, name: &str) -> InputId {
+        harness.state.graph[node_id].get_input(name).unwrap()
+    }
+
+    #[test]
+    fn drag_between_ports_connects_them() {
+        let (mut harness, a, b) = two_nodes();
+        let (out, input) = (output(&harness, a), input(&harness, b, "A"));
+
+        let responses = harness.drag(
+            harness.port_pos(out),
+            harness.port_pos(input),
+            PointerButton::Primary,
+        );
+
+        assert_eq!(harness.state.graph.connection(input), Some(out));
+        assert!(responses.iter().any(|r| matches!(
+            r,
+            NodeResponse::ConnectEventEnded { input: i, output: o } if *i == input && *o == out
+        )));
+    }
+
+    #[test]
+    fn drag_from_connected_input_disconnects_it() {
+        let (mut harness, a, b) = two_nodes();
+        let (out, input) = (output(&harness, a), input(&harness, b, "A"));
+        harness.state.graph.add_connection(out, input);
+        harness.run_frame();
+
+        let from = harness.port_pos(input);
+        let responses = harness.drag(from, from + vec2(0.0, 200.0), PointerButton::Primary);
+
+        assert_eq!(harness.state.graph.connection(input), None);
+        assert!(responses
+            .iter()
+            .any(|r| matches!(r, NodeResponse::DisconnectEvent { .. })));
+    }
+
+    #[test]
+    fn drag_from_input_keeps_connections_to_locked_nodes() {
+        let (mut harness, a, b) = two_nodes();
+        let (out, input) = (output(&harness, a), input(&harness, b, "A"));
+        harness.state.graph.add_connection(out, input);
+        harness.state.locked_nodes.insert(a);
+        harness.run_frame();
+
+        let from = harness.port_pos(input);
+        let responses = harness.drag(from, from + vec2(0.0, 200.0), PointerButton::Primary);
+
+        assert_eq!(harness.state.graph.connection(input), Some(out));
+        assert!(!responses
+            .iter()
+            .any(|r| matches!(r, NodeResponse::DisconnectEvent { .. })));
+    }
+
+    #[test]
+    fn collapsed_nodes_spread_their_ports() {
+        let (mut harness, a, b) = two_nodes();
+        let out = output(&harness, a);
+        let (input_a, input_b) = (input(&harness, b, "A"), input(&harness, b, "B"));
+        harness.state.graph.add_connection(out, input_a);
+        harness.state.graph.add_connection(out, input_b);
+        harness.state.collapsed_nodes.insert(b);
+        harness.run_frame();
+
+        let (pos_a, pos_b) = (harness.port_pos(input_a), harness.port_pos(input_b));
+        let title_bar = harness.node_rect(b);
+        assert_eq!(pos_a.x, pos_b.x);
+        assert!(pos_a.y < pos_b.y);
+        assert!(title_bar.y_range().contains(&pos_a.y));
+        assert!(title_bar.y_range().contains(&pos_b.y));
+    }
+
+    #[test]
+    fn nodes_outside_the_editor_are_culled() {
+        let (mut harness, a, b) = two_nodes();
+        let (out, input) = (output(&harness, a), input(&harness, b, "A"));
+        harness.state.graph.add_connection(out, input);
+        harness.state.node_positions[b] = pos2(3000.0, 50.0);
+
+        DRAWN_NODES.with(|drawn| drawn.borrow_mut().clear());
+        harness.run_frame();
+        assert_eq!(DRAWN_NODES.with(|drawn| drawn.borrow().clone()), vec![a]);
+
+        // The wire still reaches the estimated port of the culled node, so it
+        // can be clicked.
+        let responses = harness.click(
+            harness.port_pos(out) + vec2(30.0, 0.0),
+            PointerButton::Primary,
+        );
+        assert!(responses.iter().any(|r| matches!(
+            r,
+            NodeResponse::SelectConnection { input: i, output: o } if *i == input && *o == out
+        )));
+    }
+
+    #[test]
+    fn layout_matches_the_drawn_nodes() {
+        let (harness, a, b) = two_nodes();
+
+        let layout = harness.state.layout(&harness.ctx, harness.editor_id);
+
+        let offset = harness.editor_rect.min.to_vec2();
+        for node_id in [a, b] {
+            assert_eq!(
+                layout.node_rects[&node_id].translate(offset),
+                harness.node_rect(node_id)
+            );
+        }
+        let out = output(&harness, a);
+        assert_eq!(
+            layout.port_locations[&out.into()] + offset,
+            harness.port_pos(out)
+        );
+    }
+
+    #[test]
+    fn estimated_layout_matches_drawn_collapsed_nodes() {
+        let (mut harness, a, b) = two_nodes();
+        let out = output(&harness, a);
+        let (input_a, input_b) = (input(&harness, b, "A"), input(&harness, b, "B"));
+        harness.state.graph.add_connection(out, input_b);
+        harness.state.collapsed_nodes.insert(b);
+        harness.run_frame();
+
+        let drawn = harness.state.layout(&harness.ctx, harness.editor_id);
+        let estimated = GraphLayout::estimate(&harness.state);
+        for layout in [&drawn, &estimated] {
+            assert!(!layout.port_locations.contains_key(&input_a.into()));
+            // The only connected input sits halfway down the left side.
+            let rect = layout.node_rects[&b];
+            assert_eq!(
+                layout.port_locations[&input_b.into()],
+                pos2(rect.left(), rect.center().y)
+            );
+        }
+    }
+
+    #[test]
+    fn box_selection_selects_nodes() {
+        let (mut harness, a, b) = two_nodes();
+        let from = harness.node_rect(a).left_top() - vec2(20.0, 20.0);
+        let to = harness.node_rect(b).right_bottom() + vec2(20.0, 20.0);
+
+        harness.drag(from, to, PointerButton::Primary);
+
+        let mut selected = harness.state.selected_nodes.clone();
+        selected.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(selected, expected);
+    }
+
+    #[test]
+    fn clicking_a_node_selects_it() {
+        let (mut harness, a, _) = two_nodes();
+
+        let responses = harness.click(harness.node_title_pos(a), PointerButton::Primary);
+
+        assert_eq!(harness.state.selected_nodes, vec![a]);
+        assert!(responses
+            .iter()
+            .any(|r| matches!(r, NodeResponse::SelectNode(node) if *node == a)));
+    }
+
+    #[test]
+    fn node_finder_creates_nodes() {
+        let (mut harness, _, _) = two_nodes();
+
+        harness.click(pos2(300.0, 500.0), PointerButton::Secondary);
+        assert!(harness.state.node_finder.is_some());
+
+        harness.type_text("Add");
+        let responses = harness.key(Key::Enter);
+
+        assert_eq!(harness.state.graph.nodes.len(), 3);
+        assert!(responses
+            .iter()
+            .any(|r| matches!(r, NodeResponse::CreatedNode(_))));
+        // The finder closes in the frame after a node is created.
+        harness.run_frame();
+        assert!(harness.state.node_finder.is_none());
+    }
+
+    #[test]
+    fn reports_the_cursor_position() {
+        let (mut harness, _, _) = two_nodes();
+
+        harness.pointer_move(pos2(300.0, 500.0));
+        let response = harness.run_frame();
+        assert!(response.cursor_in_editor);
+        assert!(!response.cursor_in_finder);
+
+        harness.click(pos2(300.0, 500.0), PointerButton::Secondary);
+        harness.pointer_move(pos2(320.0, 520.0));
+        let response = harness.run_frame();
+        assert!(response.cursor_in_editor);
+        assert!(response.cursor_in_finder);
+    }
+}
@@ -84,7 +84,31 @@ pub trait DataTypeTrait<UserState>: PartialEq + Eq {
     ///     }
     /// }
     /// ```
-    fn name(&self) -> std::borrow::Cow<str>;
+    fn name(&self) -> std::borrow::Cow<'_, str>;
+
+    /// The shape of the ports of this datatype.
+    fn port_shape(&self, _user_state: &mut UserState) -> PortShape {
+        PortShape::Circle
+    }
+
+    /// Whether the ports of this datatype are only outlined. `optional` is set
+    /// for inputs that fall back to a constant value when disconnected, e.g.
+    /// return it to tell them apart from the required inputs.
+    fn port_hollow(&self, _optional: bool, _user_state: &mut UserState) -> bool {
+        false
+    }
+
+    /// The path followed by wires carrying this datatype. When `None`, the
+    /// [`GraphStyle::wire_routing`] of the editor is used.
+    fn wire_routing(&self, _user_state: &mut UserState) -> Option<WireRouting> {
+        None
+    }
+
+    /// Whether wires carrying this datatype are drawn with a dashed stroke.
+    /// Useful to tell apart special kinds of connections, like control flow.
+    fn wire_dashed(&self, _user_state: &mut UserState) -> bool {
+        false
+    }
 }
 
 /// This trait must be implemented for the `NodeData` generic parameter of the
@@ -176,6 +200,29 @@ where
     ) {
     }
 
+    /// Size limits for this node. The node can be resized by the user within
+    /// these limits by dragging its bottom-right corner.
+    fn size_constraints(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> NodeSizeConstraints {
+        NodeSizeConstraints::default()
+    }
+
+    /// The status of this node, e.g. the result of its last evaluation. When
+    /// set, the node is outlined with the color of the status, and a badge
+    /// showing the status message on hover is drawn on its top-left corner.
+    fn status(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Option<NodeStatus> {
+        None
+    }
+
     fn can_delete(
         &self,
         _node_id: NodeId,
@@ -184,6 +231,146 @@ where
     ) -> bool {
         true
     }
+
+    /// Enables the "Duplicate" entry of the node context menu, by returning
+    /// the function adding a copy of the node to the graph. Types that are
+    /// `Clone` can return [`Graph::duplicate_node`]. The editor places the copy
+    /// next to the original.
+    ///
+    /// Default implementation returns `None`, hiding the entry.
+    fn duplicate_node(
+        &self,
+        _node_id: NodeId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Option<DuplicateNodeFn<Self, Self::DataType, Self::ValueType>> {
+        None
+    }
+
+    /// Additional entries for the context menu shown when right-clicking this
+    /// node or one of its ports. `param` is the right-clicked port, or `None`
+    /// when the node itself was right-clicked. The entries are added below the
+    /// built-in ones.
+    ///
+    /// Returning any response closes the menu.
+    fn context_menu(
+        &self,
+        _ui: &mut egui::Ui,
+        _node_id: NodeId,
+        _param: Option<AnyParameterId>,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Vec<NodeResponse<Self::Response, Self>>
+    where
+        Self::Response: UserResponseTrait,
+    {
+        Default::default()
+    }
+
+    /// Additional entries for the context menu shown when right-clicking a
+    /// connection. It is invoked on the node at the receiving (input) end of
+    /// the connection, below the built-in entries.
+    ///
+    /// Returning any response closes the menu.
+    fn connection_context_menu(
+        &self,
+        _ui: &mut egui::Ui,
+        _node_id: NodeId,
+        _input: InputId,
+        _output: OutputId,
+        _graph: &Graph<Self, Self::DataType, Self::ValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Vec<NodeResponse<Self::Response, Self>>
+    where
+        Self::Response: UserResponseTrait,
+    {
+        Default::default()
+    }
+}
+
+/// Adds a copy of a node to the graph and returns its id, see
+/// [`NodeDataTrait::duplicate_node`].
+pub type DuplicateNodeFn<NodeData, DataType, ValueType> =
+    fn(&mut Graph<NodeData, DataType, ValueType>, NodeId) -> NodeId;
+
+/// The kind of a [`NodeStatus`]. Decides the badge icon and the outline color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeStatusKind {
+    Error,
+    Warning,
+    Running,
+    Cached,
+}
+
+/// The status of a node, as returned by [`NodeDataTrait::status`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeStatus {
+    pub kind: NodeStatusKind,
+    /// Shown when hovering the status badge.
+    pub message: Option<String>,
+}
+
+impl NodeStatus {
+    pub fn new(kind: NodeStatusKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: Some(message.into()),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(NodeStatusKind::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(NodeStatusKind::Warning, message)
+    }
+
+    pub fn running(message: impl Into<String>) -> Self {
+        Self::new(NodeStatusKind::Running, message)
+    }
+
+    pub fn cached(message: impl Into<String>) -> Self {
+        Self::new(NodeStatusKind::Cached, message)
+    }
+}
+
+/// Size limits of a node, as returned by [`NodeDataTrait::size_constraints`].
+/// All sizes include the margins around the node contents.
+#[derive(Clone, Copy, Debug)]
+pub struct NodeSizeConstraints {
+    /// The node can't be resized below this size.
+    pub min_size: egui::Vec2,
+    /// The node can't be resized above this size.
+    pub max_size: egui::Vec2,
+    /// The space available to the node until the user resizes it. The node
+    /// shrinks to fit its contents inside this space.
+    pub preferred_size: egui::Vec2,
+}
+
+impl NodeSizeConstraints {
+    /// The preferred size of nodes that don't override
+    /// [`NodeDataTrait::size_constraints`].
+    pub const DEFAULT_PREFERRED_SIZE: egui::Vec2 = egui::vec2(200.0, 200.0);
+
+    /// Grows `max_size` to at least `min_size` on each axis, so the
+    /// constraints can be used to clamp sizes.
+    pub fn normalized(self) -> Self {
+        Self {
+            max_size: self.max_size.max(self.min_size),
+            ..self
+        }
+    }
+}
+
+impl Default for NodeSizeConstraints {
+    fn default() -> Self {
+        Self {
+            min_size: egui::Vec2::ZERO,
+            max_size: egui::Vec2::splat(f32::INFINITY),
+            preferred_size: Self::DEFAULT_PREFERRED_SIZE,
+        }
+    }
 }
 
 /// This trait can be implemented by any user type. The trait tells the library
@@ -214,7 +401,7 @@ impl CategoryTrait for () {
     }
 }
 
-impl<'a> CategoryTrait for &'a str {
+impl CategoryTrait for &str {
     fn name(&self) -> String {
         self.to_string()
     }
@@ -251,7 +438,7 @@ pub trait NodeTemplateTrait: Clone {
     /// The return type is Cow<str> to allow returning owned or borrowed values
     /// more flexibly. Refer to the documentation for `DataTypeTrait::name` for
     /// more information
-    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<str>;
+    fn node_finder_label(&self, user_state: &mut Self::UserState) -> std::borrow::Cow<'_, str>;
 
     /// Vec of categories to which the node belongs.
     ///
@@ -262,6 +449,29 @@ pub trait NodeTemplateTrait: Clone {
         Vec::default()
     }
 
+    /// Longer, human-readable explanation of what the node does. When
+    /// present, it is shown in a tooltip when hovering the template in the
+    /// node finder.
+    fn node_finder_description(
+        &self,
+        _user_state: &mut Self::UserState,
+    ) -> Option<std::borrow::Cow<'_, str>> {
+        None
+    }
+
+    /// The inputs and outputs this template will create, previewed in the
+    /// node finder tooltip with their data type colors.
+    ///
+    /// Returns `None` by default. Implementations whose `build_node` is free of
+    /// side effects can simply return
+    /// `Some(NodeTemplateSignature::from_build_node(self, user_state))`.
+    fn node_finder_signature(
+        &self,
+        _user_state: &mut Self::UserState,
+    ) -> Option<NodeTemplateSignature<Self::DataType>> {
+        None
+    }
+
     /// Returns a descriptive name for the node kind, used in the graph.
     fn node_graph_label(&self, user_state: &mut Self::UserState) -> String;
 
@@ -279,6 +489,62 @@ pub trait NodeTemplateTrait: Clone {
     );
 }
 
+/// The parameters a [`NodeTemplateTrait`] creates, as `(name, data type)`
+/// pairs, in the same order `build_node` adds them to the node.
+#[derive(Debug, Clone)]
+pub struct NodeTemplateSignature<DataType> {
+    pub inputs: Vec<(String, DataType)>,
+    pub outputs: Vec<(String, DataType)>,
+}
+
+impl<DataType> Default for NodeTemplateSignature<DataType> {
+    fn default() -> Self {
+        Self {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+impl<DataType> NodeTemplateSignature<DataType> {
+    /// Computes the signature of `template` by running its `build_node` on a
+    /// scratch graph. Any side effects `build_node` has on `user_state` will
+    /// happen too, so only use this for side-effect free templates.
+    pub fn from_build_node<NodeTemplate, NodeData, ValueType, UserState>(
+        template: &NodeTemplate,
+        user_state: &mut UserState,
+    ) -> Self
+    where
+        NodeTemplate: NodeTemplateTrait<
+            NodeData = NodeData,
+            DataType = DataType,
+            ValueType = ValueType,
+            UserState = UserState,
+        >,
+    {
+        let mut graph = Graph::<NodeData, DataType, ValueType>::new();
+        let node_id = graph.add_node(
+            String::new(),
+            template.user_data(user_state),
+            |graph, node_id| template.build_node(graph, user_state, node_id),
+        );
+        let node = graph.nodes.remove(node_id).expect("Node should exist");
+
+        let inputs = node
+            .inputs
+            .into_iter()
+            .filter_map(|(name, id)| graph.inputs.remove(id).map(|param| (name, param.typ)))
+            .collect();
+        let outputs = node
+            .outputs
+            .into_iter()
+            .filter_map(|(name, id)| graph.outputs.remove(id).map(|param| (name, param.typ)))
+            .collect();
+
+        Self { inputs, outputs }
+    }
+}
+
 /// The custom user response types when drawing nodes in the graph must
 /// implement this trait.
 pub trait UserResponseTrait: Clone + std::fmt::Debug {}
@@ -1,4 +1,5 @@
 use super::*;
+use std::collections::{HashMap, HashSet};
 use std::marker::PhantomData;
 
 #[cfg(feature = "persistence")]
@@ -11,6 +12,52 @@ pub struct PanZoom {
     pub zoom: f32,
 }
 
+/// The element of the graph a [`ContextMenu`] was opened for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum ContextMenuTarget {
+    Connection { input: InputId, output: OutputId },
+    Node(NodeId),
+    Port(AnyParameterId),
+}
+
+/// An open right-click context menu.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub struct ContextMenu {
+    pub target: ContextMenuTarget,
+    /// Screen position where the menu was opened.
+    pub position: egui::Pos2,
+}
+
+/// Which dependencies of the hovered or selected nodes are highlighted. All
+/// other nodes and connections are dimmed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
+pub enum DependencyHighlight {
+    #[default]
+    Off,
+    /// The nodes the focused nodes depend on.
+    Upstream,
+    /// The nodes depending on the focused nodes.
+    Downstream,
+    Both,
+}
+
+/// Extra information drawn on a connection, usually to visualize the data
+/// flowing through it. See [`GraphEditorState::annotate_connection`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectionAnnotation {
+    /// Draws a halo of this color below the wire.
+    pub highlight: Option<egui::Color32>,
+    /// Speed of the dots marching from the output to the input, in points per
+    /// second. Overrides [`GraphStyle::flow_speed`]. Set to `Some(0.0)` to
+    /// hide the dots of this connection.
+    pub flow_speed: Option<f32>,
+    /// Short text drawn at the middle of the wire, e.g. the value it carries.
+    pub label: Option<String>,
+}
+
 #[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(Serialize, Deserialize))]
 pub struct GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserState> {
@@ -24,14 +71,73 @@ pub struct GraphEditorState<NodeData, DataType, ValueType, NodeTemplate, UserSta
     /// The currently selected node. Some interface actions depend on the
     /// currently selected node.
     pub selected_nodes: Vec<NodeId>,
+    /// The currently selected connections, as `(input, output)` pairs.
+    /// Pressing the Delete key removes them.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub selected_connections: Vec<(InputId, OutputId)>,
     /// The mouse drag start position for an ongoing box selection.
     pub ongoing_box_selection: Option<egui::Pos2>,
+    /// The node currently being dragged around by the user, if any. Dropping
+    /// it on top of a connection inserts it into that connection.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub ongoing_node_drag: Option<NodeId>,
+    /// The path traced so far by an ongoing knife gesture (Ctrl + right
+    /// drag). Every connection crossed by the path is removed on release.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub ongoing_knife_cut: Option<Vec<egui::Pos2>>,
     /// The position of each node.
     pub node_positions: SecondaryMap<NodeId, egui::Pos2>,
+    /// The size of each node the user has resized. Nodes without an entry
+    /// use their preferred size, see [`NodeDataTrait::size_constraints`].
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub node_sizes: SecondaryMap<NodeId, egui::Vec2>,
+    /// Nodes that are currently collapsed, showing only their title bar.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub collapsed_nodes: HashSet<NodeId>,
+    /// When set, the graph can be panned, zoomed and selected but not edited:
+    /// Nodes can't be created, deleted, moved or resized, connections can't
+    /// change and the value widgets are disabled.
+    ///
+    /// The UI hooks of [`NodeDataTrait`] (`top_bar_ui`, `output_ui`,
+    /// `bottom_ui`, `context_menu` and `connection_context_menu`) stay live
+    /// and their responses are still applied, so they can keep showing
+    /// information and triggering user actions. Hooks that edit the graph
+    /// should check this flag themselves, e.g. by mirroring it in their
+    /// `UserState`.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub read_only: bool,
+    /// Nodes that can't be edited, as if the editor was in `read_only` mode.
+    /// Connections to these nodes can't change either.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub locked_nodes: HashSet<NodeId>,
+    /// Stable, human-readable identifiers of the nodes, used instead of the
+    /// node ids by the document format of the `persistence` feature. Nodes
+    /// get one the first time they are exported.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub stable_ids: SecondaryMap<NodeId, String>,
     /// The node finder is used to create new nodes.
     pub node_finder: Option<NodeFinder<NodeTemplate>>,
+    /// When set, all connections show dots marching from their output to
+    /// their input, to visualize the data flow.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub show_data_flow: bool,
+    /// Highlights the dependencies of the hovered node or, when no node is
+    /// hovered, of the selected ones.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub dependency_highlight: DependencyHighlight,
+    /// Per-connection annotations, indexed by `(input, output)`. Annotations
+    /// of connections that no longer exist are dropped.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub connection_annotations: HashMap<(InputId, OutputId), ConnectionAnnotation>,
+    /// The right-click context menu, if open.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub context_menu: Option<ContextMenu>,
     /// The panning of the graph viewport.
     pub pan_zoom: PanZoom,
+    /// The style used to draw the editor. When `None`, the dark or light
+    /// preset is picked following the current egui visuals.
+    #[cfg_attr(feature = "persistence", serde(default))]
+    pub style: Option<GraphStyle>,
     pub _user_state: PhantomData<fn() -> UserState>,
 }
 
@@ -48,6 +154,66 @@ impl<NodeData, DataType, ValueType, NodeKind, UserState>
         }
     }
 }
+impl<NodeData, DataType, ValueType, NodeKind, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeKind, UserState>
+{
+    /// Whether the node can't be edited, either because it is locked or because
+    /// the editor is in read-only mode.
+    pub fn is_node_locked(&self, node_id: NodeId) -> bool {
+        self.read_only || self.locked_nodes.contains(&node_id)
+    }
+
+    /// Whether the connection `output -> input` can't be removed, because one
+    /// of its nodes is locked.
+    pub fn is_connection_locked(&self, input: InputId, output: OutputId) -> bool {
+        let node_locked =
+            |node: Option<NodeId>| node.map(|node| self.is_node_locked(node)).unwrap_or(false);
+        node_locked(self.graph.try_get_input(input).map(|p| p.node))
+            || node_locked(self.graph.try_get_output(output).map(|p| p.node))
+    }
+
+    /// The annotation of the connection `output -> input`, created empty if
+    /// it doesn't exist yet.
+    pub fn annotate_connection(
+        &mut self,
+        input: InputId,
+        output: OutputId,
+    ) -> &mut ConnectionAnnotation {
+        self.connection_annotations
+            .entry((input, output))
+            .or_default()
+    }
+
+    /// Places `new_node`, a copy of `node_id`, slightly offset from the
+    /// original and on top of the other nodes.
+    pub(crate) fn place_duplicate(&mut self, node_id: NodeId, new_node: NodeId) {
+        self.node_positions.insert(
+            new_node,
+            self.node_positions[node_id] + egui::vec2(30.0, 30.0),
+        );
+        if let Some(size) = self.node_sizes.get(node_id).copied() {
+            self.node_sizes.insert(new_node, size);
+        }
+        self.node_order.push(new_node);
+    }
+}
+
+impl<NodeData, DataType, ValueType, NodeKind, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeKind, UserState>
+where
+    NodeData: Clone,
+    DataType: Clone,
+    ValueType: Clone,
+{
+    /// Adds a copy of the node, without its connections, slightly offset from
+    /// the original. Returns the id of the new node.
+    pub fn duplicate_node(&mut self, node_id: NodeId) -> NodeId {
+        let new_node = self.graph.duplicate_node(node_id);
+        self.place_duplicate(node_id, new_node);
+        new_node
+    }
+}
+
 impl<NodeData, DataType, ValueType, NodeKind, UserState> Default
     for GraphEditorState<NodeData, DataType, ValueType, NodeKind, UserState>
 {
@@ -57,10 +223,23 @@ impl<NodeData, DataType, ValueType, NodeKind, UserState> Default
             node_order: Default::default(),
             connection_in_progress: Default::default(),
             selected_nodes: Default::default(),
+            selected_connections: Default::default(),
             ongoing_box_selection: Default::default(),
+            ongoing_node_drag: Default::default(),
+            ongoing_knife_cut: Default::default(),
             node_positions: Default::default(),
+            node_sizes: Default::default(),
+            collapsed_nodes: Default::default(),
+            read_only: Default::default(),
+            locked_nodes: Default::default(),
+            stable_ids: Default::default(),
             node_finder: Default::default(),
+            show_data_flow: Default::default(),
+            dependency_highlight: Default::default(),
+            connection_annotations: Default::default(),
+            context_menu: Default::default(),
             pan_zoom: Default::default(),
+            style: Default::default(),
             _user_state: Default::default(),
         }
     }
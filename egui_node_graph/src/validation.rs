@@ -0,0 +1,374 @@
+use super::*;
+use std::collections::HashSet;
+
+/// A broken invariant of a [`Graph`] or a [`GraphEditorState`], as reported by
+/// [`Graph::validate`] and [`GraphEditorState::validate`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum GraphViolation {
+    #[error(
+        "The connection from {output:?} to {input:?} refers to a parameter that doesn't exist"
+    )]
+    DanglingConnection { input: InputId, output: OutputId },
+
+    #[error("The connection from {output:?} to {input:?} links parameters of different types")]
+    ConnectionTypeMismatch { input: InputId, output: OutputId },
+
+    #[error("Node {node:?} lists the parameter {param:?}, which doesn't exist")]
+    MissingParam { node: NodeId, param: AnyParameterId },
+
+    #[error("Node {node:?} lists the parameter {param:?}, which belongs to node {owner:?}")]
+    ParamNodeMismatch {
+        node: NodeId,
+        param: AnyParameterId,
+        owner: NodeId,
+    },
+
+    #[error("The parameter {param:?} belongs to node {owner:?}, which doesn't list it")]
+    UnlistedParam {
+        param: AnyParameterId,
+        owner: NodeId,
+    },
+
+    #[error("Node {node:?} has more than one parameter named {name}")]
+    DuplicateParamName {
+        node: NodeId,
+        name: String,
+        param: AnyParameterId,
+    },
+
+    #[error("Node {0:?} has no position")]
+    MissingNodePosition(NodeId),
+
+    #[error("Node {0:?} is missing from the node order")]
+    MissingFromNodeOrder(NodeId),
+
+    #[error("The node order contains {0:?}, which is not in the graph")]
+    UnknownNodeInOrder(NodeId),
+
+    #[error("The node order contains {0:?} more than once")]
+    DuplicateInNodeOrder(NodeId),
+}
+
+/// The parameters listed by a node, inputs first, with their names.
+fn listed_params<NodeData>(
+    node: &Node<NodeData>,
+) -> impl Iterator<Item = (&str, AnyParameterId)> + '_ {
+    let inputs = node
+        .inputs
+        .iter()
+        .map(|(name, id)| (name.as_str(), AnyParameterId::Input(*id)));
+    let outputs = node
+        .outputs
+        .iter()
+        .map(|(name, id)| (name.as_str(), AnyParameterId::Output(*id)));
+    inputs.chain(outputs)
+}
+
+impl<NodeData, DataType, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// The node a parameter points to with its back-reference, if the
+    /// parameter exists.
+    fn param_owner(&self, param: AnyParameterId) -> Option<NodeId> {
+        match param {
+            AnyParameterId::Input(input) => self.inputs.get(input).map(|p| p.node),
+            AnyParameterId::Output(output) => self.outputs.get(output).map(|p| p.node),
+        }
+    }
+
+    fn set_param_owner(&mut self, param: AnyParameterId, node_id: NodeId) {
+        match param {
+            AnyParameterId::Input(input) => self.inputs[input].node = node_id,
+            AnyParameterId::Output(output) => self.outputs[output].node = node_id,
+        }
+    }
+
+    fn lists_param(&self, node_id: NodeId, param: AnyParameterId) -> bool {
+        self.nodes
+            .get(node_id)
+            .map(|node| listed_params(node).any(|(_, id)| id == param))
+            .unwrap_or(false)
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType> Graph<NodeData, DataType, ValueType> {
+    /// Checks the invariants of the graph that the editor relies on. Returns
+    /// an empty list when the graph is consistent. Graphs built through the
+    /// methods of this crate always are, but deserialized or hand-edited ones
+    /// may not be.
+    pub fn validate(&self) -> Vec<GraphViolation> {
+        let mut violations = Vec::new();
+
+        for (node_id, node) in &self.nodes {
+            let mut names = HashSet::new();
+            for (name, param) in listed_params(node) {
+                match self.param_owner(param) {
+                    None => violations.push(GraphViolation::MissingParam {
+                        node: node_id,
+                        param,
+                    }),
+                    Some(owner) if owner != node_id => {
+                        violations.push(GraphViolation::ParamNodeMismatch {
+                            node: node_id,
+                            param,
+                            owner,
+                        })
+                    }
+                    Some(_) => {}
+                }
+                // Inputs and outputs are looked up separately, so they may
+                // share names.
+                let is_input = matches!(param, AnyParameterId::Input(_));
+                if !names.insert((is_input, name)) {
+                    violations.push(GraphViolation::DuplicateParamName {
+                        node: node_id,
+                        name: name.to_owned(),
+                        param,
+                    });
+                }
+            }
+        }
+
+        let params = (self.inputs.iter())
+            .map(|(id, param)| (AnyParameterId::Input(id), param.node))
+            .chain(
+                (self.outputs.iter()).map(|(id, param)| (AnyParameterId::Output(id), param.node)),
+            );
+        for (param, owner) in params {
+            if !self.lists_param(owner, param) {
+                violations.push(GraphViolation::UnlistedParam { param, owner });
+            }
+        }
+
+        for (input, output) in self.iter_connections() {
+            match (self.inputs.get(input), self.outputs.get(output)) {
+                (Some(input_param), Some(output_param)) => {
+                    if input_param.typ != output_param.typ {
+                        violations.push(GraphViolation::ConnectionTypeMismatch { input, output });
+                    }
+                }
+                _ => violations.push(GraphViolation::DanglingConnection { input, output }),
+            }
+        }
+
+        violations
+    }
+
+    /// Fixes the violations reported by [`Self::validate`] where possible:
+    /// - Nodes stop listing parameters that don't exist or belong to another
+    ///   node. A parameter with a broken back-reference is given to the first
+    ///   node listing it.
+    /// - Parameters not listed by their node are removed.
+    /// - Dangling and type-mismatched connections are removed.
+    ///
+    /// Duplicate parameter names are left as is, since renaming parameters
+    /// would break the lookups by name of the user code. Returns the
+    /// violations that remain after the repair.
+    pub fn repair(&mut self) -> Vec<GraphViolation> {
+        for node_id in self.iter_nodes().collect::<Vec<_>>() {
+            let listed: Vec<_> = listed_params(&self.nodes[node_id])
+                .map(|(_, param)| param)
+                .collect();
+            for param in listed {
+                let keep = match self.param_owner(param) {
+                    None => false,
+                    Some(owner) if owner == node_id => true,
+                    Some(owner) if self.lists_param(owner, param) => false,
+                    Some(_) => {
+                        self.set_param_owner(param, node_id);
+                        true
+                    }
+                };
+                if !keep {
+                    let node = &mut self.nodes[node_id];
+                    node.inputs
+                        .retain(|(_, id)| AnyParameterId::Input(*id) != param);
+                    node.outputs
+                        .retain(|(_, id)| AnyParameterId::Output(*id) != param);
+                }
+            }
+        }
+
+        let nodes = &self.nodes;
+        let lists = |node_id: NodeId, param: AnyParameterId| {
+            nodes
+                .get(node_id)
+                .map(|node| listed_params(node).any(|(_, id)| id == param))
+                .unwrap_or(false)
+        };
+        self.inputs
+            .retain(|id, param| lists(param.node, AnyParameterId::Input(id)));
+        self.outputs
+            .retain(|id, param| lists(param.node, AnyParameterId::Output(id)));
+
+        let (inputs, outputs) = (&self.inputs, &self.outputs);
+        self.connections.retain(
+            |input, output| match (inputs.get(input), outputs.get(*output)) {
+                (Some(input), Some(output)) => input.typ == output.typ,
+                _ => false,
+            },
+        );
+
+        self.validate()
+    }
+}
+
+impl<NodeData, DataType: PartialEq, ValueType, NodeKind, UserState>
+    GraphEditorState<NodeData, DataType, ValueType, NodeKind, UserState>
+{
+    /// Checks the invariants of the graph, see [`Graph::validate`], and that
+    /// every node has a position and appears exactly once in the node order.
+    /// Returns an empty list when the state is consistent.
+    pub fn validate(&self) -> Vec<GraphViolation> {
+        let mut violations = self.graph.validate();
+
+        let mut ordered = HashSet::new();
+        for node_id in &self.node_order {
+            if !self.graph.nodes.contains_key(*node_id) {
+                violations.push(GraphViolation::UnknownNodeInOrder(*node_id));
+            } else if !ordered.insert(*node_id) {
+                violations.push(GraphViolation::DuplicateInNodeOrder(*node_id));
+            }
+        }
+        for node_id in self.graph.iter_nodes() {
+            if !ordered.contains(&node_id) {
+                violations.push(GraphViolation::MissingFromNodeOrder(node_id));
+            }
+            if !self.node_positions.contains_key(node_id) {
+                violations.push(GraphViolation::MissingNodePosition(node_id));
+            }
+        }
+
+        violations
+    }
+
+    /// Repairs the graph, see [`Graph::repair`], and brings the rest of the
+    /// editor state in sync with it. Nodes missing from the node order are
+    /// drawn on top, and nodes without a position are placed at the origin.
+    /// References to nodes and connections that no longer exist are dropped.
+    ///
+    /// Call this after loading a state from a file. Returns the violations
+    /// that remain after the repair.
+    pub fn repair(&mut self) -> Vec<GraphViolation> {
+        self.graph.repair();
+        let graph = &self.graph;
+        let exists = |node_id: &NodeId| graph.nodes.contains_key(*node_id);
+        let connected = |input: InputId, output: OutputId| graph.connection(input) == Some(output);
+
+        let mut ordered = HashSet::new();
+        self.node_order
+            .retain(|node_id| exists(node_id) && ordered.insert(*node_id));
+        for node_id in graph.iter_nodes() {
+            if !ordered.contains(&node_id) {
+                self.node_order.push(node_id);
+            }
+            if !self.node_positions.contains_key(node_id) {
+                self.node_positions.insert(node_id, egui::Pos2::ZERO);
+            }
+        }
+
+        self.node_positions.retain(|node_id, _| exists(&node_id));
+        self.node_sizes.retain(|node_id, _| exists(&node_id));
+        self.stable_ids.retain(|node_id, _| exists(&node_id));
+        self.collapsed_nodes.retain(exists);
+        self.locked_nodes.retain(exists);
+        self.selected_nodes.retain(exists);
+        self.selected_connections
+            .retain(|(input, output)| connected(*input, *output));
+        self.connection_annotations
+            .retain(|(input, output), _| connected(*input, *output));
+        self.connection_in_progress = self
+            .connection_in_progress
+            .filter(|(node_id, param)| graph.param_owner(*param) == Some(*node_id));
+        self.ongoing_node_drag = self.ongoing_node_drag.filter(exists);
+
+        self.validate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestGraph = Graph<(), u32, ()>;
+
+    /// A graph with two nodes, each with an input and an output of type `0`,
+    /// connected `a.out -> b.in`.
+    fn two_nodes() -> (TestGraph, NodeId, NodeId) {
+        let mut graph = TestGraph::new();
+        let add_node = |graph: &mut TestGraph| {
+            graph.add_node("node".into(), (), |graph, node_id| {
+                graph.add_input_param(
+                    node_id,
+                    "in".into(),
+                    0,
+                    (),
+                    InputParamKind::ConnectionOrConstant,
+                    true,
+                );
+                graph.add_output_param(node_id, "out".into(), 0);
+            })
+        };
+        let a = add_node(&mut graph);
+        let b = add_node(&mut graph);
+        let (output, input) = (
+            graph[a].get_output("out").unwrap(),
+            graph[b].get_input("in").unwrap(),
+        );
+        graph.add_connection(output, input);
+        (graph, a, b)
+    }
+
+    #[test]
+    fn consistent_graph_has_no_violations() {
+        let (graph, _, _) = two_nodes();
+        assert_eq!(graph.validate(), vec![]);
+    }
+
+    #[test]
+    fn repair_fixes_broken_references() {
+        let (mut graph, a, b) = two_nodes();
+        let input = graph[b].get_input("in").unwrap();
+        let output = graph[a].get_output("out").unwrap();
+        graph.inputs[input].typ = 1;
+        graph.outputs[output].node = b;
+        graph.nodes[a].inputs.push(("in".into(), input));
+
+        let violations = graph.validate();
+        assert!(violations.contains(&GraphViolation::ConnectionTypeMismatch { input, output }));
+        assert!(violations.contains(&GraphViolation::ParamNodeMismatch {
+            node: a,
+            param: AnyParameterId::Output(output),
+            owner: b,
+        }));
+        assert!(violations.contains(&GraphViolation::DuplicateParamName {
+            node: a,
+            name: "in".into(),
+            param: AnyParameterId::Input(input),
+        }));
+
+        assert_eq!(graph.repair(), vec![]);
+        assert_eq!(graph.connection(input), None);
+        assert_eq!(graph[output].node, a);
+        assert_eq!(graph[a].inputs.len(), 1);
+    }
+
+    #[test]
+    fn editor_repair_syncs_node_order_and_positions() {
+        let (graph, a, b) = two_nodes();
+        let mut state = GraphEditorState::<(), u32, (), (), ()>::new(1.0);
+        state.graph = graph;
+        state.node_order = vec![a, a];
+        state.node_positions.insert(a, egui::pos2(10.0, 10.0));
+
+        assert_eq!(
+            state.validate(),
+            vec![
+                GraphViolation::DuplicateInNodeOrder(a),
+                GraphViolation::MissingFromNodeOrder(b),
+                GraphViolation::MissingNodePosition(b),
+            ]
+        );
+        assert_eq!(state.repair(), vec![]);
+        assert_eq!(state.node_order, vec![a, b]);
+        assert_eq!(state.node_positions[b], egui::Pos2::ZERO);
+    }
+}
@@ -0,0 +1,149 @@
+//! Derive macros for [`egui_node_graph`](https://docs.rs/egui_node_graph).
+//! Enable the `derive` feature of `egui_node_graph` to use them through its
+//! re-exports.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod node_template;
+mod widget_value;
+
+/// Implements `NodeTemplateTrait` for an enum of unit variants, and defines a
+/// unit struct implementing `NodeTemplateIter` with all the variants.
+///
+/// The enum takes a `#[node_template(...)]` attribute with:
+/// - `node_data`, `data_type`, `value_type` and `user_state`: The associated
+///   types of the trait.
+/// - `category_type`: Optional, `&'static str` by default.
+/// - `user_data`: Optional expression returning the node data. Defaults to
+///   `Default::default()`.
+/// - `iter`: Optional name of the `NodeTemplateIter` struct. Defaults to
+///   `All{Enum}s`.
+///
+/// Each variant can take a `#[node_template(...)]` attribute with:
+/// - `label`, `graph_label`, `category` and `description`, all optional. The
+///   label defaults to the name of the variant and the graph label to the
+///   label. `category` can be repeated.
+/// - Any number of `input(name, data_type, value)`, optionally followed by
+///   `kind = ` an `InputParamKind` variant (`ConnectionOrConstant` by default)
+///   and `shown_inline = ` a bool (`true` by default).
+/// - Any number of `output(name, data_type)`.
+///
+/// All the expressions can use `self` and `user_state`, the
+/// `&mut Self::UserState` passed to the trait methods.
+///
+/// The parameters are added in the order they are listed, inputs first.
+///
+/// ```
+/// use egui_node_graph::*;
+/// use egui_node_graph_derive::NodeTemplate;
+///
+/// #[derive(Clone, PartialEq, Eq)]
+/// enum DataType {
+///     Scalar,
+/// }
+///
+/// #[derive(Clone, Copy)]
+/// struct NodeData {
+///     template: Template,
+/// }
+///
+/// #[derive(Clone, Copy, NodeTemplate)]
+/// #[node_template(
+///     node_data = NodeData,
+///     data_type = DataType,
+///     value_type = f32,
+///     user_state = (),
+///     user_data = NodeData { template: *self },
+/// )]
+/// enum Template {
+///     #[node_template(
+///         label = "New scalar",
+///         category = "Scalar",
+///         input("value", DataType::Scalar, 0.0),
+///         output("out", DataType::Scalar),
+///     )]
+///     MakeScalar,
+///     #[node_template(
+///         label = "Scalar add",
+///         category = "Scalar",
+///         input("A", DataType::Scalar, 0.0),
+///         input("B", DataType::Scalar, 0.0, kind = ConnectionOnly),
+///         output("out", DataType::Scalar),
+///     )]
+///     AddScalar,
+/// }
+///
+/// assert_eq!(AllTemplates.all_kinds().len(), 2);
+/// ```
+#[proc_macro_derive(NodeTemplate, attributes(node_template))]
+pub fn derive_node_template(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    node_template::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `WidgetValueTrait` for an enum or a struct whose fields
+/// implement `InlineWidget`, e.g. numbers, `bool`, `String`, `egui::Vec2`,
+/// `egui::Color32` and `ComboBoxValue` enums. The fields are drawn in a row
+/// after the parameter name, labeled with their name when there is more than
+/// one.
+///
+/// The type takes a `#[widget_value(response = ..., user_state = ...,
+/// node_data = ...)]` attribute with the associated types of the trait. Fields
+/// and variants can take `#[widget(speed = ..., range = min..=max)]`, passed to
+/// the widgets as `WidgetOptions`.
+///
+/// For enums, every variant with a single field also gets a `try_to_*`
+/// accessor named after it in snake case, returning a clone of the field or
+/// a `WrongValueKind` error.
+///
+/// ```
+/// use egui_node_graph::*;
+/// use egui_node_graph_derive::{ComboBoxValue, WidgetValue};
+///
+/// #[derive(Clone, Copy, PartialEq, ComboBoxValue)]
+/// enum BlendMode {
+///     Add,
+///     #[widget(label = "Multiply")]
+///     Mul,
+/// }
+///
+/// #[derive(Clone, PartialEq, WidgetValue)]
+/// #[widget_value(response = (), user_state = (), node_data = ())]
+/// enum Value {
+///     #[widget(speed = 0.01, range = 0.0..=1.0)]
+///     Scalar(f32),
+///     Vec2 { value: egui::Vec2 },
+///     Color(egui::Color32),
+///     Blend(BlendMode),
+/// }
+///
+/// impl Default for Value {
+///     fn default() -> Self {
+///         Self::Scalar(0.0)
+///     }
+/// }
+///
+/// assert_eq!(Value::Scalar(0.5).try_to_scalar().unwrap(), 0.5);
+/// assert!(Value::Scalar(0.5).try_to_vec2().is_err());
+/// ```
+#[proc_macro_derive(WidgetValue, attributes(widget_value, widget))]
+pub fn derive_widget_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    widget_value::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `ComboBoxValue` for an enum of unit variants, listing them in
+/// order, and `InlineWidget` with a combo box. Variants are labeled with
+/// their name, or with the label given in `#[widget(label = ...)]`.
+#[proc_macro_derive(ComboBoxValue, attributes(widget))]
+pub fn derive_combo_box_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    widget_value::derive_combo_box(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
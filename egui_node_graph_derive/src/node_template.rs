@@ -0,0 +1,363 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{Data, DeriveInput, Expr, Fields, Ident, LitStr, Token, Type};
+
+/// The `#[node_template(...)]` attribute of the enum.
+struct TemplateAttrs {
+    node_data: Type,
+    data_type: Type,
+    value_type: Type,
+    user_state: Type,
+    category_type: Type,
+    user_data: Option<Expr>,
+    iter: Option<Ident>,
+}
+
+impl TemplateAttrs {
+    fn from_input(input: &DeriveInput) -> syn::Result<Self> {
+        let (mut node_data, mut data_type, mut value_type, mut user_state) =
+            (None, None, None, None);
+        let mut category_type = None;
+        let mut user_data = None;
+        let mut iter = None;
+        for attr in &input.attrs {
+            if !attr.path().is_ident("node_template") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                let key = meta.path.get_ident().map(|ident| ident.to_string());
+                match key.as_deref() {
+                    Some("node_data") => node_data = Some(meta.value()?.parse()?),
+                    Some("data_type") => data_type = Some(meta.value()?.parse()?),
+                    Some("value_type") => value_type = Some(meta.value()?.parse()?),
+                    Some("user_state") => user_state = Some(meta.value()?.parse()?),
+                    Some("category_type") => category_type = Some(meta.value()?.parse()?),
+                    Some("user_data") => user_data = Some(meta.value()?.parse()?),
+                    Some("iter") => iter = Some(meta.value()?.parse()?),
+                    _ => return Err(meta.error("unknown `node_template` attribute")),
+                }
+                Ok(())
+            })?;
+        }
+
+        let required = |value: Option<Type>, name: &str| {
+            value.ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &input.ident,
+                    format!("missing `#[node_template({name} = ...)]` attribute"),
+                )
+            })
+        };
+        Ok(Self {
+            node_data: required(node_data, "node_data")?,
+            data_type: required(data_type, "data_type")?,
+            value_type: required(value_type, "value_type")?,
+            user_state: required(user_state, "user_state")?,
+            category_type: category_type.unwrap_or_else(|| syn::parse_quote!(&'static str)),
+            user_data,
+            iter,
+        })
+    }
+}
+
+/// An `input(name, data_type, value, kind = ..., shown_inline = ...)` entry
+/// of a variant's `#[node_template(...)]` attribute.
+struct InputAttr {
+    name: Expr,
+    typ: Expr,
+    value: Expr,
+    kind: Option<Ident>,
+    shown_inline: Option<Expr>,
+}
+
+impl Parse for InputAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let typ = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let value = input.parse()?;
+        let (mut kind, mut shown_inline) = (None, None);
+        while input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            if key == "kind" {
+                kind = Some(input.parse()?);
+            } else if key == "shown_inline" {
+                shown_inline = Some(input.parse()?);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `kind` or `shown_inline`",
+                ));
+            }
+        }
+        Ok(Self {
+            name,
+            typ,
+            value,
+            kind,
+            shown_inline,
+        })
+    }
+}
+
+/// An `output(name, data_type)` entry of a variant's `#[node_template(...)]`
+/// attribute.
+struct OutputAttr {
+    name: Expr,
+    typ: Expr,
+}
+
+impl Parse for OutputAttr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let typ = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(Self { name, typ })
+    }
+}
+
+/// The attributes of a variant.
+struct VariantAttrs {
+    ident: Ident,
+    label: Expr,
+    graph_label: Option<Expr>,
+    categories: Vec<Expr>,
+    description: Option<Expr>,
+    inputs: Vec<InputAttr>,
+    outputs: Vec<OutputAttr>,
+}
+
+impl VariantAttrs {
+    fn from_variant(variant: &syn::Variant) -> syn::Result<Self> {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "`NodeTemplate` can only be derived for enums with unit variants",
+            ));
+        }
+
+        let mut label = None;
+        let mut graph_label = None;
+        let mut categories = Vec::new();
+        let mut description = None;
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for attr in &variant.attrs {
+            if !attr.path().is_ident("node_template") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                let key = meta.path.get_ident().map(|ident| ident.to_string());
+                match key.as_deref() {
+                    Some("label") => label = Some(meta.value()?.parse()?),
+                    Some("graph_label") => graph_label = Some(meta.value()?.parse()?),
+                    Some("category") => categories.push(meta.value()?.parse()?),
+                    Some("description") => description = Some(meta.value()?.parse()?),
+                    Some("input") => {
+                        let content;
+                        syn::parenthesized!(content in meta.input);
+                        inputs.push(content.parse()?);
+                    }
+                    Some("output") => {
+                        let content;
+                        syn::parenthesized!(content in meta.input);
+                        outputs.push(content.parse()?);
+                    }
+                    _ => return Err(meta.error("unknown `node_template` attribute")),
+                }
+                Ok(())
+            })?;
+        }
+
+        let ident = variant.ident.clone();
+        let label = label.unwrap_or_else(|| {
+            let name = LitStr::new(&ident.to_string(), ident.span());
+            syn::parse_quote!(#name)
+        });
+        Ok(Self {
+            ident,
+            label,
+            graph_label,
+            categories,
+            description,
+            inputs,
+            outputs,
+        })
+    }
+
+    fn build_node(&self) -> TokenStream {
+        let inputs = self.inputs.iter().map(|input| {
+            let InputAttr {
+                name, typ, value, ..
+            } = input;
+            let kind =
+                (input.kind.clone()).unwrap_or_else(|| format_ident!("ConnectionOrConstant"));
+            let shown_inline =
+                (input.shown_inline.clone()).unwrap_or_else(|| syn::parse_quote!(true));
+            quote! {
+                graph.add_input_param(
+                    node_id,
+                    ::std::string::String::from(#name),
+                    #typ,
+                    #value,
+                    ::egui_node_graph::InputParamKind::#kind,
+                    #shown_inline,
+                );
+            }
+        });
+        let outputs = self.outputs.iter().map(|OutputAttr { name, typ }| {
+            quote! {
+                graph.add_output_param(node_id, ::std::string::String::from(#name), #typ);
+            }
+        });
+        quote! { #(#inputs)* #(#outputs)* }
+    }
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`NodeTemplate` can only be derived for enums",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`NodeTemplate` can't be derived for generic enums",
+        ));
+    }
+
+    let attrs = TemplateAttrs::from_input(&input)?;
+    let variants = (data.variants.iter())
+        .map(VariantAttrs::from_variant)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let TemplateAttrs {
+        node_data,
+        data_type,
+        value_type,
+        user_state,
+        category_type,
+        ..
+    } = &attrs;
+    let user_data = (attrs.user_data.clone())
+        .unwrap_or_else(|| syn::parse_quote!(::std::default::Default::default()));
+    let iter = (attrs.iter.clone()).unwrap_or_else(|| format_ident!("All{}s", ident));
+    let iter_doc = format!("All the variants of [`{ident}`], for the node finder.");
+
+    let names: Vec<_> = variants.iter().map(|variant| &variant.ident).collect();
+    let labels = variants.iter().map(|variant| &variant.label);
+    let graph_labels = variants.iter().map(|variant| match &variant.graph_label {
+        Some(graph_label) => quote!(::std::string::String::from(#graph_label)),
+        None => quote!(::std::string::String::from(
+            ::egui_node_graph::NodeTemplateTrait::node_finder_label(self, user_state)
+        )),
+    });
+    let categories = variants.iter().map(|variant| {
+        let categories = &variant.categories;
+        quote!(::std::vec![#(#categories),*])
+    });
+    let descriptions = variants.iter().map(|variant| match &variant.description {
+        Some(description) => quote!(::std::option::Option::Some(
+            ::std::borrow::Cow::from(#description)
+        )),
+        None => quote!(::std::option::Option::None),
+    });
+    let build_nodes = variants.iter().map(VariantAttrs::build_node);
+
+    // The attribute expressions can use `user_state`, even in the methods
+    // that don't need it otherwise.
+    Ok(quote! {
+        #[allow(unused_variables)]
+        impl ::egui_node_graph::NodeTemplateTrait for #ident {
+            type NodeData = #node_data;
+            type DataType = #data_type;
+            type ValueType = #value_type;
+            type UserState = #user_state;
+            type CategoryType = #category_type;
+
+            fn node_finder_label(
+                &self,
+                user_state: &mut Self::UserState,
+            ) -> ::std::borrow::Cow<'_, str> {
+                match *self {
+                    #(Self::#names => ::std::borrow::Cow::from(#labels),)*
+                }
+            }
+
+            fn node_finder_categories(
+                &self,
+                user_state: &mut Self::UserState,
+            ) -> ::std::vec::Vec<Self::CategoryType> {
+                match *self {
+                    #(Self::#names => #categories,)*
+                }
+            }
+
+            fn node_finder_description(
+                &self,
+                user_state: &mut Self::UserState,
+            ) -> ::std::option::Option<::std::borrow::Cow<'_, str>> {
+                match *self {
+                    #(Self::#names => #descriptions,)*
+                }
+            }
+
+            fn node_finder_signature(
+                &self,
+                user_state: &mut Self::UserState,
+            ) -> ::std::option::Option<::egui_node_graph::NodeTemplateSignature<Self::DataType>> {
+                ::std::option::Option::Some(
+                    ::egui_node_graph::NodeTemplateSignature::from_build_node(self, user_state),
+                )
+            }
+
+            fn node_graph_label(&self, user_state: &mut Self::UserState) -> ::std::string::String {
+                match *self {
+                    #(Self::#names => #graph_labels,)*
+                }
+            }
+
+            fn user_data(&self, user_state: &mut Self::UserState) -> Self::NodeData {
+                #user_data
+            }
+
+            fn build_node(
+                &self,
+                graph: &mut ::egui_node_graph::Graph<
+                    Self::NodeData,
+                    Self::DataType,
+                    Self::ValueType,
+                >,
+                user_state: &mut Self::UserState,
+                node_id: ::egui_node_graph::NodeId,
+            ) {
+                match *self {
+                    #(Self::#names => { #build_nodes })*
+                }
+            }
+        }
+
+        #[doc = #iter_doc]
+        #[derive(Clone, Copy, Debug, Default)]
+        #vis struct #iter;
+
+        impl ::egui_node_graph::NodeTemplateIter for #iter {
+            type Item = #ident;
+
+            fn all_kinds(&self) -> ::std::vec::Vec<#ident> {
+                ::std::vec![#(#ident::#names),*]
+            }
+        }
+    })
+}
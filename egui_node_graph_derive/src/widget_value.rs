@@ -0,0 +1,360 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Expr, Fields, Ident, LitStr, RangeLimits, Type};
+
+/// The `#[widget_value(...)]` attribute of the type.
+struct WidgetValueAttrs {
+    response: Type,
+    user_state: Type,
+    node_data: Type,
+}
+
+impl WidgetValueAttrs {
+    fn from_input(input: &DeriveInput) -> syn::Result<Self> {
+        let (mut response, mut user_state, mut node_data) = (None, None, None);
+        for attr in &input.attrs {
+            if !attr.path().is_ident("widget_value") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                let key = meta.path.get_ident().map(|ident| ident.to_string());
+                match key.as_deref() {
+                    Some("response") => response = Some(meta.value()?.parse()?),
+                    Some("user_state") => user_state = Some(meta.value()?.parse()?),
+                    Some("node_data") => node_data = Some(meta.value()?.parse()?),
+                    _ => return Err(meta.error("unknown `widget_value` attribute")),
+                }
+                Ok(())
+            })?;
+        }
+
+        let required = |value: Option<Type>, name: &str| {
+            value.ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &input.ident,
+                    format!("missing `#[widget_value({name} = ...)]` attribute"),
+                )
+            })
+        };
+        Ok(Self {
+            response: required(response, "response")?,
+            user_state: required(user_state, "user_state")?,
+            node_data: required(node_data, "node_data")?,
+        })
+    }
+}
+
+/// The `#[widget(speed = ..., range = a..=b)]` attribute of a field or a
+/// variant. The settings of a field override the ones of its variant.
+#[derive(Clone, Default)]
+struct WidgetAttrs {
+    speed: Option<Expr>,
+    range: Option<(Expr, Expr)>,
+}
+
+impl WidgetAttrs {
+    fn from_attrs(attrs: &[Attribute], mut parsed: Self) -> syn::Result<Self> {
+        for attr in attrs {
+            if !attr.path().is_ident("widget") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("speed") {
+                    parsed.speed = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("range") {
+                    match meta.value()?.parse()? {
+                        Expr::Range(syn::ExprRange {
+                            start: Some(start),
+                            limits: RangeLimits::Closed(_),
+                            end: Some(end),
+                            ..
+                        }) => parsed.range = Some((*start, *end)),
+                        range => {
+                            return Err(syn::Error::new_spanned(
+                                range,
+                                "expected an inclusive range, e.g. `0.0..=1.0`",
+                            ))
+                        }
+                    }
+                } else {
+                    return Err(meta.error("expected `speed` or `range`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(parsed)
+    }
+
+    fn options(&self) -> TokenStream {
+        let speed = match &self.speed {
+            Some(speed) => quote!(::std::option::Option::Some((#speed) as f64)),
+            None => quote!(::std::option::Option::None),
+        };
+        let range = match &self.range {
+            Some((start, end)) => {
+                quote!(::std::option::Option::Some((#start) as f64..=(#end) as f64))
+            }
+            None => quote!(::std::option::Option::None),
+        };
+        quote! {
+            ::egui_node_graph::WidgetOptions {
+                speed: #speed,
+                range: #range,
+            }
+        }
+    }
+}
+
+/// The bindings of the fields of a variant or struct, and the code drawing
+/// their widgets next to the parameter name.
+fn fields_widget(fields: &Fields, attrs: &WidgetAttrs) -> syn::Result<(TokenStream, TokenStream)> {
+    let bindings: Vec<Ident> = (fields.iter().enumerate())
+        .map(|(i, field)| {
+            field
+                .ident
+                .clone()
+                .unwrap_or_else(|| format_ident!("field_{i}"))
+        })
+        .collect();
+    let pattern = match fields {
+        Fields::Named(_) => quote!({ #(#bindings),* }),
+        Fields::Unnamed(_) => quote!(( #(#bindings),* )),
+        Fields::Unit => quote!(),
+    };
+
+    let mut widgets = Vec::new();
+    for (field, binding) in fields.iter().zip(&bindings) {
+        // Fields are only labeled when there is more than one of them.
+        let label = match &field.ident {
+            Some(ident) if fields.len() > 1 => {
+                let name = LitStr::new(&ident.to_string(), ident.span());
+                quote!(ui.label(#name);)
+            }
+            _ => quote!(),
+        };
+        let options = WidgetAttrs::from_attrs(&field.attrs, attrs.clone())?.options();
+        widgets.push(quote! {
+            #label
+            ::egui_node_graph::InlineWidget::inline_widget(#binding, ui, &#options);
+        });
+    }
+    let widget = quote! {
+        ui.horizontal(|ui| {
+            ui.label(param_name);
+            #(#widgets)*
+        });
+    };
+    Ok((pattern, widget))
+}
+
+fn snake_case(ident: &Ident) -> String {
+    let mut snake = String::new();
+    let mut previous_lower = false;
+    for c in ident.to_string().chars() {
+        if c.is_uppercase() && previous_lower {
+            snake.push('_');
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// `try_to_*` accessors for the variants with exactly one field.
+fn accessors(ident: &Ident, variants: &[&syn::Variant]) -> TokenStream {
+    let names = variants.iter().map(|variant| &variant.ident);
+    let kind_names = names.clone().map(|name| name.to_string());
+    let patterns = variants.iter().map(|variant| match variant.fields {
+        Fields::Named(_) => quote!({ .. }),
+        Fields::Unnamed(_) => quote!((..)),
+        Fields::Unit => quote!(),
+    });
+    let kind_name = quote! {
+        match other {
+            #(Self::#names #patterns => #kind_names,)*
+        }
+    };
+
+    let accessors = variants.iter().filter_map(|variant| {
+        let field = match &variant.fields {
+            Fields::Named(fields) if fields.named.len() == 1 => &fields.named[0],
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0],
+            _ => return None,
+        };
+        let name = &variant.ident;
+        let kind = name.to_string();
+        let typ = &field.ty;
+        let pattern = match &field.ident {
+            Some(field) => quote!({ #field: value }),
+            None => quote!((value)),
+        };
+        let function = format_ident!("try_to_{}", snake_case(name));
+        let doc = format!("Tries to downcast this value to a [`{ident}::{name}`].");
+        let other_arm = (variants.len() > 1).then(|| {
+            quote! {
+                other => ::std::result::Result::Err(
+                    ::egui_node_graph::WrongValueKind {
+                        expected: #kind,
+                        found: #kind_name,
+                    },
+                ),
+            }
+        });
+        Some(quote! {
+            #[doc = #doc]
+            pub fn #function(&self) -> ::std::result::Result<#typ, ::egui_node_graph::WrongValueKind> {
+                match self {
+                    Self::#name #pattern => ::std::result::Result::Ok(::std::clone::Clone::clone(value)),
+                    #other_arm
+                }
+            }
+        })
+    });
+    quote! {
+        impl #ident {
+            #(#accessors)*
+        }
+    }
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`WidgetValue` can't be derived for generic types",
+        ));
+    }
+    let WidgetValueAttrs {
+        response,
+        user_state,
+        node_data,
+    } = WidgetValueAttrs::from_input(&input)?;
+    let ident = &input.ident;
+
+    let (body, accessors) = match &input.data {
+        Data::Enum(data) => {
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let attrs = WidgetAttrs::from_attrs(&variant.attrs, WidgetAttrs::default())?;
+                let (pattern, widget) = fields_widget(&variant.fields, &attrs)?;
+                let name = &variant.ident;
+                arms.push(quote!(Self::#name #pattern => { #widget }));
+            }
+            let variants: Vec<_> = data.variants.iter().collect();
+            let body = quote! {
+                match self {
+                    #(#arms)*
+                }
+            };
+            (body, accessors(ident, &variants))
+        }
+        Data::Struct(data) => {
+            let (pattern, widget) = fields_widget(&data.fields, &WidgetAttrs::default())?;
+            let body = quote! {
+                let Self #pattern = self;
+                #widget
+            };
+            (body, quote!())
+        }
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "`WidgetValue` can't be derived for unions",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl ::egui_node_graph::WidgetValueTrait for #ident {
+            type Response = #response;
+            type UserState = #user_state;
+            type NodeData = #node_data;
+
+            fn value_widget(
+                &mut self,
+                param_name: &str,
+                _node_id: ::egui_node_graph::NodeId,
+                ui: &mut ::egui_node_graph::egui::Ui,
+                _user_state: &mut Self::UserState,
+                _node_data: &Self::NodeData,
+            ) -> ::std::vec::Vec<Self::Response> {
+                #body
+                ::std::vec::Vec::new()
+            }
+        }
+
+        #accessors
+    })
+}
+
+pub fn derive_combo_box(input: DeriveInput) -> syn::Result<TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`ComboBoxValue` can only be derived for enums",
+            ))
+        }
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "`ComboBoxValue` can't be derived for generic enums",
+        ));
+    }
+
+    let mut names = Vec::new();
+    let mut labels = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "`ComboBoxValue` can only be derived for enums with unit variants",
+            ));
+        }
+        let mut label: Expr = {
+            let name = LitStr::new(&variant.ident.to_string(), variant.ident.span());
+            syn::parse_quote!(#name)
+        };
+        for attr in &variant.attrs {
+            if attr.path().is_ident("widget") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("label") {
+                        label = meta.value()?.parse()?;
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `label`"))
+                    }
+                })?;
+            }
+        }
+        names.push(&variant.ident);
+        labels.push(label);
+    }
+
+    let ident = &input.ident;
+    Ok(quote! {
+        impl ::egui_node_graph::ComboBoxValue for #ident {
+            fn all_values() -> ::std::vec::Vec<Self> {
+                ::std::vec![#(Self::#names),*]
+            }
+
+            fn label(&self) -> ::std::borrow::Cow<'_, str> {
+                match *self {
+                    #(Self::#names => ::std::borrow::Cow::from(#labels),)*
+                }
+            }
+        }
+
+        impl ::egui_node_graph::InlineWidget for #ident {
+            fn inline_widget(
+                &mut self,
+                ui: &mut ::egui_node_graph::egui::Ui,
+                _options: &::egui_node_graph::WidgetOptions,
+            ) -> ::egui_node_graph::egui::Response {
+                ::egui_node_graph::combo_box_widget(self, ui)
+            }
+        }
+    })
+}
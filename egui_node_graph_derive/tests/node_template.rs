@@ -0,0 +1,144 @@
+use egui_node_graph::*;
+use egui_node_graph_derive::NodeTemplate;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum DataType {
+    Scalar,
+    Vec2,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Scalar(f32),
+    Vec2(f32, f32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, NodeTemplate)]
+#[node_template(
+    node_data = Template,
+    data_type = DataType,
+    value_type = Value,
+    user_state = (),
+    user_data = *self,
+    iter = Templates,
+)]
+enum Template {
+    #[node_template(
+        label = "New scalar",
+        category = "Scalar",
+        description = "A constant.",
+        input("value", DataType::Scalar, Value::Scalar(1.0), kind = ConstantOnly),
+        output("out", DataType::Scalar),
+    )]
+    MakeScalar,
+    #[node_template(
+        label = "Vector times scalar",
+        graph_label = "Scale",
+        category = "Vector",
+        category = "Scalar",
+        input("scalar", DataType::Scalar, Value::Scalar(0.0)),
+        input("vector", DataType::Vec2, Value::Vec2(0.0, 0.0), shown_inline = false),
+        output("out", DataType::Vec2)
+    )]
+    VectorTimesScalar,
+    Empty,
+}
+
+/// Counts the nodes built so far.
+#[derive(Default)]
+struct Counter(u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, NodeTemplate)]
+#[node_template(
+    node_data = u32,
+    data_type = DataType,
+    value_type = Value,
+    user_state = Counter,
+    user_data = user_state.0,
+)]
+enum Numbered {
+    #[node_template(
+        graph_label = format!("Scalar #{}", user_state.0),
+        input("value", DataType::Scalar, Value::Scalar(user_state.0 as f32)),
+    )]
+    Scalar,
+}
+
+#[test]
+fn derives_finder_information() {
+    let user_state = &mut ();
+    assert_eq!(
+        Templates.all_kinds(),
+        vec![
+            Template::MakeScalar,
+            Template::VectorTimesScalar,
+            Template::Empty
+        ]
+    );
+
+    let scale = Template::VectorTimesScalar;
+    assert_eq!(scale.node_finder_label(user_state), "Vector times scalar");
+    assert_eq!(scale.node_graph_label(user_state), "Scale");
+    assert_eq!(
+        scale.node_finder_categories(user_state),
+        vec!["Vector", "Scalar"]
+    );
+    assert_eq!(scale.node_finder_description(user_state), None);
+
+    let make = Template::MakeScalar;
+    assert_eq!(make.node_graph_label(user_state), "New scalar");
+    assert_eq!(
+        make.node_finder_description(user_state).as_deref(),
+        Some("A constant.")
+    );
+    assert_eq!(Template::Empty.node_finder_label(user_state), "Empty");
+    assert!(Template::Empty
+        .node_finder_categories(user_state)
+        .is_empty());
+}
+
+#[test]
+fn derives_build_node() {
+    let mut graph = Graph::<Template, DataType, Value>::new();
+    let template = Template::VectorTimesScalar;
+    let node_id = graph.add_node(
+        template.node_graph_label(&mut ()),
+        template.user_data(&mut ()),
+        |graph, node_id| template.build_node(graph, &mut (), node_id),
+    );
+
+    let node = &graph[node_id];
+    assert_eq!(node.user_data, template);
+    let vector = &graph[node.get_input("vector").unwrap()];
+    assert_eq!(vector.typ, DataType::Vec2);
+    assert_eq!(vector.value, Value::Vec2(0.0, 0.0));
+    assert!(!vector.shown_inline);
+    assert_eq!(graph[node.get_output("out").unwrap()].typ, DataType::Vec2);
+
+    let signature = Template::MakeScalar.node_finder_signature(&mut ()).unwrap();
+    assert_eq!(
+        signature.inputs,
+        vec![("value".to_owned(), DataType::Scalar)]
+    );
+}
+
+#[test]
+fn expressions_can_use_the_user_state() {
+    let mut graph = Graph::<u32, DataType, Value>::new();
+    let user_state = &mut Counter(3);
+    let template = Numbered::Scalar;
+    assert_eq!(template.node_graph_label(user_state), "Scalar #3");
+    let node_id = graph.add_node(
+        template.node_graph_label(user_state),
+        template.user_data(user_state),
+        |graph, node_id| template.build_node(graph, user_state, node_id),
+    );
+
+    let node = &graph[node_id];
+    assert_eq!(node.user_data, 3);
+    assert_eq!(
+        graph[node.get_input("value").unwrap()].value,
+        Value::Scalar(3.0)
+    );
+    assert_eq!(AllNumbereds.all_kinds(), vec![Numbered::Scalar]);
+}
@@ -0,0 +1,171 @@
+use egui_node_graph::*;
+use egui_node_graph_derive::{ComboBoxValue, WidgetValue};
+
+#[derive(Clone, Copy, Debug, PartialEq, ComboBoxValue)]
+enum Interpolation {
+    Linear,
+    #[widget(label = "Smooth step")]
+    SmoothStep,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, WidgetValue)]
+#[widget_value(response = (), user_state = (), node_data = ())]
+enum Value {
+    #[widget(speed = 0.1, range = 0.0..=1.0)]
+    Scalar(f32),
+    Count {
+        #[widget(range = 0..=10)]
+        value: i32,
+    },
+    Flag(bool),
+    Name(String),
+    Vec2(egui::Vec2),
+    Color(egui::Color32),
+    Interpolation(Interpolation),
+    Rect {
+        min: egui::Vec2,
+        max: egui::Vec2,
+    },
+    #[default]
+    Nothing,
+}
+
+#[derive(Clone, Default, WidgetValue)]
+#[widget_value(response = (), user_state = (), node_data = ())]
+struct Gradient {
+    start: egui::Color32,
+    end: egui::Color32,
+    opacity: Percent,
+}
+
+#[derive(Clone, Default)]
+struct Percent(f32);
+
+impl InlineWidget for Percent {
+    fn inline_widget(&mut self, ui: &mut egui::Ui, _options: &WidgetOptions) -> egui::Response {
+        ui.add(egui::DragValue::new(&mut self.0).suffix("%"))
+    }
+}
+
+#[test]
+fn derives_accessors() {
+    assert_eq!(Value::Scalar(0.5).try_to_scalar().unwrap(), 0.5);
+    assert_eq!(Value::Count { value: 3 }.try_to_count().unwrap(), 3);
+    assert_eq!(
+        Value::Name("a".into()).try_to_name().unwrap(),
+        "a".to_owned()
+    );
+    assert_eq!(
+        Value::Interpolation(Interpolation::Linear)
+            .try_to_interpolation()
+            .unwrap(),
+        Interpolation::Linear
+    );
+    assert_eq!(
+        Value::Flag(true).try_to_vec2(),
+        Err(WrongValueKind {
+            expected: "Vec2",
+            found: "Flag"
+        })
+    );
+}
+
+#[test]
+fn derives_combo_box_values() {
+    assert_eq!(
+        Interpolation::all_values(),
+        vec![Interpolation::Linear, Interpolation::SmoothStep]
+    );
+    assert_eq!(Interpolation::SmoothStep.label(), "Smooth step");
+}
+
+#[test]
+fn draws_every_kind_of_value() {
+    let ctx = egui::Context::default();
+    let mut values = vec![
+        Value::Scalar(0.5),
+        Value::Count { value: 3 },
+        Value::Flag(true),
+        Value::Name("a".into()),
+        Value::Vec2(egui::vec2(1.0, 2.0)),
+        Value::Color(egui::Color32::RED),
+        Value::Interpolation(Interpolation::SmoothStep),
+        Value::Rect {
+            min: egui::Vec2::ZERO,
+            max: egui::Vec2::ZERO,
+        },
+        Value::Nothing,
+    ];
+    let mut gradient = Gradient::default();
+    let node_id = NodeId::default();
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            for value in &mut values {
+                assert!(value
+                    .value_widget("param", node_id, ui, &mut (), &())
+                    .is_empty());
+            }
+            gradient.value_widget("gradient", node_id, ui, &mut (), &());
+        });
+    });
+}
+
+/// Records the options its widget is drawn with.
+#[derive(Clone, Default)]
+struct Recorder(Vec<WidgetOptions>);
+
+impl InlineWidget for Recorder {
+    fn inline_widget(&mut self, ui: &mut egui::Ui, options: &WidgetOptions) -> egui::Response {
+        self.0.push(options.clone());
+        ui.label("recorder")
+    }
+}
+
+#[derive(Clone, Default, WidgetValue)]
+#[widget_value(response = (), user_state = (), node_data = ())]
+enum Recorded {
+    #[default]
+    Nothing,
+    #[widget(speed = 0.5, range = 0..=10)]
+    Pair {
+        inherited: Recorder,
+        #[widget(range = -1.0..=1.0)]
+        overridden: Recorder,
+    },
+}
+
+#[test]
+fn passes_widget_options() {
+    let ctx = egui::Context::default();
+    let mut value = Recorded::Pair {
+        inherited: Recorder::default(),
+        overridden: Recorder::default(),
+    };
+    let _ = ctx.run(egui::RawInput::default(), |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            value.value_widget("param", NodeId::default(), ui, &mut (), &());
+        });
+    });
+
+    let (inherited, overridden) = match value {
+        Recorded::Pair {
+            inherited,
+            overridden,
+        } => (inherited, overridden),
+        Recorded::Nothing => unreachable!(),
+    };
+    assert_eq!(
+        inherited.0,
+        vec![WidgetOptions {
+            speed: Some(0.5),
+            range: Some(0.0..=10.0),
+        }]
+    );
+    assert_eq!(
+        overridden.0,
+        vec![WidgetOptions {
+            speed: Some(0.5),
+            range: Some(-1.0..=1.0),
+        }]
+    );
+}
@@ -1,6 +1,6 @@
 use std::{borrow::Cow, collections::HashMap};
 
-use eframe::egui::{self, DragValue, TextStyle};
+use eframe::egui::{self, TextStyle};
 use egui_node_graph::*;
 
 // ========= First, define your user data types =============
@@ -8,6 +8,7 @@ use egui_node_graph::*;
 /// The NodeData holds a custom data struct inside each node. It's useful to
 /// store additional information that doesn't live in parameters. For this
 /// example, the node data stores the template (i.e. the "type") of the node.
+#[derive(Clone)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct MyNodeData {
     template: MyNodeTemplate,
@@ -16,7 +17,7 @@ pub struct MyNodeData {
 /// `DataType`s are what defines the possible range of connections when
 /// attaching two ports together. The graph UI will make sure to not allow
 /// attaching incompatible datatypes.
-#[derive(PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub enum MyDataType {
     Scalar,
@@ -30,8 +31,15 @@ pub enum MyDataType {
 /// this library makes no attempt to check this consistency. For instance, it is
 /// up to the user code in this example to make sure no parameter is created
 /// with a DataType of Scalar and a ValueType of Vec2.
-#[derive(Copy, Clone, Debug)]
+///
+/// The `WidgetValueTrait` tells the library which UI to display for the inline
+/// parameter widgets. `#[derive(WidgetValue)]` implements it by drawing a
+/// widget for each field next to the parameter name, and also generates the
+/// `try_to_vec2` and `try_to_scalar` accessors used by the evaluator below.
+/// Implement the trait by hand when the widgets need to return responses.
+#[derive(Copy, Clone, Debug, WidgetValue)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+#[widget_value(response = MyResponse, user_state = MyGraphState, node_data = MyNodeData)]
 pub enum MyValueType {
     Vec2 { value: egui::Vec2 },
     Scalar { value: f32 },
@@ -45,38 +53,100 @@ impl Default for MyValueType {
     }
 }
 
-impl MyValueType {
-    /// Tries to downcast this value type to a vector
-    pub fn try_to_vec2(self) -> anyhow::Result<egui::Vec2> {
-        if let MyValueType::Vec2 { value } = self {
-            Ok(value)
-        } else {
-            anyhow::bail!("Invalid cast from {:?} to vec2", self)
-        }
-    }
-
-    /// Tries to downcast this value type to a scalar
-    pub fn try_to_scalar(self) -> anyhow::Result<f32> {
-        if let MyValueType::Scalar { value } = self {
-            Ok(value)
-        } else {
-            anyhow::bail!("Invalid cast from {:?} to scalar", self)
-        }
-    }
-}
-
 /// NodeTemplate is a mechanism to define node templates. It's what the graph
 /// will display in the "new node" popup. The user code needs to tell the
 /// library how to convert a NodeTemplate into a Node.
-#[derive(Clone, Copy)]
+///
+/// This is done by implementing `NodeTemplateTrait`. For a plain enum like this
+/// one, `#[derive(NodeTemplate)]` implements it from the attributes below, and
+/// also defines `AllMyNodeTemplates`, listing the variants in order for the
+/// node finder. Implement the trait by hand when you need more control, e.g.
+/// a `build_node` that depends on the graph or has side effects.
+#[derive(Clone, Copy, NodeTemplate)]
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
+#[node_template(
+    node_data = MyNodeData,
+    data_type = MyDataType,
+    value_type = MyValueType,
+    user_state = MyGraphState,
+    // The node data stored in every node built from a template.
+    user_data = MyNodeData { template: *self },
+    iter = AllMyNodeTemplates,
+)]
 pub enum MyNodeTemplate {
+    // The label is what the node finder shows, and also the title of the node
+    // unless a `graph_label` is given. The categories are what allow the node
+    // finder to show collapsible lists. The description is optional, and shown
+    // in a tooltip when hovering the template in the node finder, along with
+    // the signature of the node.
+    //
+    // The nodes are created empty, so each template lists the inputs and
+    // outputs to create. An input takes the name of the parameter, which can
+    // be later used to retrieve its value and should be unique, its data type
+    // and the default value of its inline widget. It accepts both connections
+    // and constants unless another `kind` is given.
+    #[node_template(
+        label = "New scalar",
+        category = "Scalar",
+        description = "Outputs a constant scalar value.",
+        input("value", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        output("out", MyDataType::Scalar),
+    )]
     MakeScalar,
+    #[node_template(
+        label = "New vector",
+        category = "Vector",
+        description = "Builds a 2d vector from its x and y components.",
+        input("x", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        input("y", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        output("out", MyDataType::Vec2),
+    )]
+    MakeVector,
+    #[node_template(
+        label = "Scalar add",
+        category = "Scalar",
+        description = "Adds two scalars together.",
+        input("A", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        input("B", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        output("out", MyDataType::Scalar),
+    )]
     AddScalar,
+    #[node_template(
+        label = "Scalar subtract",
+        category = "Scalar",
+        description = "Subtracts scalar B from scalar A.",
+        input("A", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        input("B", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        output("out", MyDataType::Scalar),
+    )]
     SubtractScalar,
-    MakeVector,
+    #[node_template(
+        label = "Vector add",
+        category = "Vector",
+        description = "Adds two 2d vectors together.",
+        input("v1", MyDataType::Vec2, MyValueType::Vec2 { value: egui::vec2(0.0, 0.0) }),
+        input("v2", MyDataType::Vec2, MyValueType::Vec2 { value: egui::vec2(0.0, 0.0) }),
+        output("out", MyDataType::Vec2),
+    )]
     AddVector,
+    #[node_template(
+        label = "Vector subtract",
+        category = "Vector",
+        description = "Subtracts vector v2 from vector v1.",
+        input("v1", MyDataType::Vec2, MyValueType::Vec2 { value: egui::vec2(0.0, 0.0) }),
+        input("v2", MyDataType::Vec2, MyValueType::Vec2 { value: egui::vec2(0.0, 0.0) }),
+        output("out", MyDataType::Vec2),
+    )]
     SubtractVector,
+    #[node_template(
+        label = "Vector times scalar",
+        category = "Vector",
+        category = "Scalar",
+        description = "Scales a 2d vector by a scalar.",
+        input("scalar", MyDataType::Scalar, MyValueType::Scalar { value: 0.0 }),
+        input("vector", MyDataType::Vec2, MyValueType::Vec2 { value: egui::vec2(0.0, 0.0) }),
+        output("out", MyDataType::Vec2),
+    )]
     VectorTimesScalar,
 }
 
@@ -97,6 +167,10 @@ pub enum MyResponse {
 #[cfg_attr(feature = "persistence", derive(serde::Serialize, serde::Deserialize))]
 pub struct MyGraphState {
     pub active_node: Option<NodeId>,
+    /// The error produced by the last evaluation of the active node, if any,
+    /// and the node that failed.
+    #[cfg_attr(feature = "persistence", serde(skip))]
+    pub evaluation_error: Option<(NodeId, String)>,
 }
 
 // =========== Then, you need to implement some traits ============
@@ -116,210 +190,48 @@ impl DataTypeTrait<MyGraphState> for MyDataType {
             MyDataType::Vec2 => Cow::Borrowed("2d vector"),
         }
     }
-}
 
-// A trait for the node kinds, which tells the library how to build new nodes
-// from the templates in the node finder
-impl NodeTemplateTrait for MyNodeTemplate {
-    type NodeData = MyNodeData;
-    type DataType = MyDataType;
-    type ValueType = MyValueType;
-    type UserState = MyGraphState;
-    type CategoryType = &'static str;
-
-    fn node_finder_label(&self, _user_state: &mut Self::UserState) -> Cow<'_, str> {
-        Cow::Borrowed(match self {
-            MyNodeTemplate::MakeScalar => "New scalar",
-            MyNodeTemplate::AddScalar => "Scalar add",
-            MyNodeTemplate::SubtractScalar => "Scalar subtract",
-            MyNodeTemplate::MakeVector => "New vector",
-            MyNodeTemplate::AddVector => "Vector add",
-            MyNodeTemplate::SubtractVector => "Vector subtract",
-            MyNodeTemplate::VectorTimesScalar => "Vector times scalar",
-        })
-    }
-
-    // this is what allows the library to show collapsible lists in the node finder.
-    fn node_finder_categories(&self, _user_state: &mut Self::UserState) -> Vec<&'static str> {
+    fn port_shape(&self, _user_state: &mut MyGraphState) -> PortShape {
         match self {
-            MyNodeTemplate::MakeScalar
-            | MyNodeTemplate::AddScalar
-            | MyNodeTemplate::SubtractScalar => vec!["Scalar"],
-            MyNodeTemplate::MakeVector
-            | MyNodeTemplate::AddVector
-            | MyNodeTemplate::SubtractVector => vec!["Vector"],
-            MyNodeTemplate::VectorTimesScalar => vec!["Vector", "Scalar"],
+            MyDataType::Scalar => PortShape::Circle,
+            MyDataType::Vec2 => PortShape::Diamond,
         }
     }
+}
 
-    fn node_graph_label(&self, user_state: &mut Self::UserState) -> String {
-        // It's okay to delegate this to node_finder_label if you don't want to
-        // show different names in the node finder and the node itself.
-        self.node_finder_label(user_state).into()
-    }
-
-    fn user_data(&self, _user_state: &mut Self::UserState) -> Self::NodeData {
-        MyNodeData { template: *self }
-    }
+impl UserResponseTrait for MyResponse {}
+impl NodeDataTrait for MyNodeData {
+    type Response = MyResponse;
+    type UserState = MyGraphState;
+    type DataType = MyDataType;
+    type ValueType = MyValueType;
 
-    fn build_node(
+    // Evaluation errors are shown as a badge on the node that failed, which
+    // may be upstream of the active node.
+    fn status(
         &self,
-        graph: &mut Graph<Self::NodeData, Self::DataType, Self::ValueType>,
-        _user_state: &mut Self::UserState,
         node_id: NodeId,
-    ) {
-        // The nodes are created empty by default. This function needs to take
-        // care of creating the desired inputs and outputs based on the template
-
-        // We define some closures here to avoid boilerplate. Note that this is
-        // entirely optional.
-        let input_scalar = |graph: &mut MyGraph, name: &str| {
-            graph.add_input_param(
-                node_id,
-                name.to_string(),
-                MyDataType::Scalar,
-                MyValueType::Scalar { value: 0.0 },
-                InputParamKind::ConnectionOrConstant,
-                true,
-            );
-        };
-        let input_vector = |graph: &mut MyGraph, name: &str| {
-            graph.add_input_param(
-                node_id,
-                name.to_string(),
-                MyDataType::Vec2,
-                MyValueType::Vec2 {
-                    value: egui::vec2(0.0, 0.0),
-                },
-                InputParamKind::ConnectionOrConstant,
-                true,
-            );
-        };
-
-        let output_scalar = |graph: &mut MyGraph, name: &str| {
-            graph.add_output_param(node_id, name.to_string(), MyDataType::Scalar);
-        };
-        let output_vector = |graph: &mut MyGraph, name: &str| {
-            graph.add_output_param(node_id, name.to_string(), MyDataType::Vec2);
-        };
-
-        match self {
-            MyNodeTemplate::AddScalar => {
-                // The first input param doesn't use the closure so we can comment
-                // it in more detail.
-                graph.add_input_param(
-                    node_id,
-                    // This is the name of the parameter. Can be later used to
-                    // retrieve the value. Parameter names should be unique.
-                    "A".into(),
-                    // The data type for this input. In this case, a scalar
-                    MyDataType::Scalar,
-                    // The value type for this input. We store zero as default
-                    MyValueType::Scalar { value: 0.0 },
-                    // The input parameter kind. This allows defining whether a
-                    // parameter accepts input connections and/or an inline
-                    // widget to set its value.
-                    InputParamKind::ConnectionOrConstant,
-                    true,
-                );
-                input_scalar(graph, "B");
-                output_scalar(graph, "out");
-            }
-            MyNodeTemplate::SubtractScalar => {
-                input_scalar(graph, "A");
-                input_scalar(graph, "B");
-                output_scalar(graph, "out");
-            }
-            MyNodeTemplate::VectorTimesScalar => {
-                input_scalar(graph, "scalar");
-                input_vector(graph, "vector");
-                output_vector(graph, "out");
-            }
-            MyNodeTemplate::AddVector => {
-                input_vector(graph, "v1");
-                input_vector(graph, "v2");
-                output_vector(graph, "out");
-            }
-            MyNodeTemplate::SubtractVector => {
-                input_vector(graph, "v1");
-                input_vector(graph, "v2");
-                output_vector(graph, "out");
-            }
-            MyNodeTemplate::MakeVector => {
-                input_scalar(graph, "x");
-                input_scalar(graph, "y");
-                output_vector(graph, "out");
-            }
-            MyNodeTemplate::MakeScalar => {
-                input_scalar(graph, "value");
-                output_scalar(graph, "out");
+        _graph: &Graph<MyNodeData, MyDataType, MyValueType>,
+        user_state: &mut Self::UserState,
+    ) -> Option<NodeStatus> {
+        match &user_state.evaluation_error {
+            Some((failed_node, err)) if *failed_node == node_id => {
+                Some(NodeStatus::error(format!("Execution error: {}", err)))
             }
+            _ => None,
         }
     }
-}
-
-pub struct AllMyNodeTemplates;
-impl NodeTemplateIter for AllMyNodeTemplates {
-    type Item = MyNodeTemplate;
-
-    fn all_kinds(&self) -> Vec<Self::Item> {
-        // This function must return a list of node kinds, which the node finder
-        // will use to display it to the user. Crates like strum can reduce the
-        // boilerplate in enumerating all variants of an enum.
-        vec![
-            MyNodeTemplate::MakeScalar,
-            MyNodeTemplate::MakeVector,
-            MyNodeTemplate::AddScalar,
-            MyNodeTemplate::SubtractScalar,
-            MyNodeTemplate::AddVector,
-            MyNodeTemplate::SubtractVector,
-            MyNodeTemplate::VectorTimesScalar,
-        ]
-    }
-}
 
-impl WidgetValueTrait for MyValueType {
-    type Response = MyResponse;
-    type UserState = MyGraphState;
-    type NodeData = MyNodeData;
-    fn value_widget(
-        &mut self,
-        param_name: &str,
+    // Our types are `Clone`, so nodes can be duplicated from their context
+    // menu.
+    fn duplicate_node(
+        &self,
         _node_id: NodeId,
-        ui: &mut egui::Ui,
-        _user_state: &mut MyGraphState,
-        _node_data: &MyNodeData,
-    ) -> Vec<MyResponse> {
-        // This trait is used to tell the library which UI to display for the
-        // inline parameter widgets.
-        match self {
-            MyValueType::Vec2 { value } => {
-                ui.label(param_name);
-                ui.horizontal(|ui| {
-                    ui.label("x");
-                    ui.add(DragValue::new(&mut value.x));
-                    ui.label("y");
-                    ui.add(DragValue::new(&mut value.y));
-                });
-            }
-            MyValueType::Scalar { value } => {
-                ui.horizontal(|ui| {
-                    ui.label(param_name);
-                    ui.add(DragValue::new(value));
-                });
-            }
-        }
-        // This allows you to return your responses from the inline widgets.
-        Vec::new()
+        _graph: &MyGraph,
+        _user_state: &mut Self::UserState,
+    ) -> Option<DuplicateNodeFn<MyNodeData, MyDataType, MyValueType>> {
+        Some(MyGraph::duplicate_node)
     }
-}
-
-impl UserResponseTrait for MyResponse {}
-impl NodeDataTrait for MyNodeData {
-    type Response = MyResponse;
-    type UserState = MyGraphState;
-    type DataType = MyDataType;
-    type ValueType = MyValueType;
 
     // This method will be called when drawing each node. This allows adding
     // extra ui elements inside the nodes. In this case, we create an "active"
@@ -366,6 +278,23 @@ impl NodeDataTrait for MyNodeData {
 
         responses
     }
+
+    // Right-clicking a node shows a context menu with some built-in actions.
+    // This method can add more entries to it.
+    fn context_menu(
+        &self,
+        ui: &mut egui::Ui,
+        node_id: NodeId,
+        param: Option<AnyParameterId>,
+        _graph: &Graph<MyNodeData, MyDataType, MyValueType>,
+        _user_state: &mut Self::UserState,
+    ) -> Vec<NodeResponse<MyResponse, MyNodeData>> {
+        let mut responses = vec![];
+        if param.is_none() && ui.button("👁 Set active").clicked() {
+            responses.push(NodeResponse::User(MyResponse::SetActiveNode(node_id)));
+        }
+        responses
+    }
 }
 
 type MyGraph = Graph<MyNodeData, MyDataType, MyValueType>;
@@ -379,24 +308,55 @@ pub struct NodeGraphExample {
     state: MyEditorState,
 
     user_state: MyGraphState,
+
+    /// A previous save that couldn't be loaded, kept to be written back.
+    #[cfg(feature = "persistence")]
+    unreadable_save: Option<String>,
+    /// Why the previous save couldn't be loaded, shown until dismissed.
+    #[cfg(feature = "persistence")]
+    load_error: Option<String>,
 }
 
 #[cfg(feature = "persistence")]
 const PERSISTENCE_KEY: &str = "egui_node_graph";
+/// Where a save that can't be read is moved to, so it isn't lost.
+#[cfg(feature = "persistence")]
+const BACKUP_KEY: &str = "egui_node_graph_unreadable";
 
 #[cfg(feature = "persistence")]
 impl NodeGraphExample {
     /// If the persistence feature is enabled, Called once before the first frame.
     /// Load previous app state (if any).
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let state = cc
-            .storage
-            .and_then(|storage| eframe::get_value(storage, PERSISTENCE_KEY))
-            .unwrap_or_default();
-        Self {
-            state,
-            user_state: MyGraphState::default(),
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            if let Some(save) = storage.get_string(PERSISTENCE_KEY) {
+                match Self::load_state(storage) {
+                    Ok(state) => app.state = state,
+                    Err(err) => {
+                        app.load_error = Some(format!(
+                            "Could not load the saved graph, keeping it as {BACKUP_KEY:?}: {err}"
+                        ));
+                        app.unreadable_save = Some(save);
+                    }
+                }
+            }
         }
+        app.state.repair();
+        app
+    }
+
+    /// Loads the saved editor state, falling back to the bare state saved by
+    /// older versions of this example, before `SaveEnvelope` existed.
+    fn load_state(storage: &dyn eframe::Storage) -> Result<MyEditorState, String> {
+        let envelope_error = match eframe::get_value::<SaveEnvelope>(storage, PERSISTENCE_KEY) {
+            Some(envelope) => match MyEditorState::load(envelope, &()) {
+                Ok(state) => return Ok(state),
+                Err(err) => err.to_string(),
+            },
+            None => "unknown save format".to_owned(),
+        };
+        eframe::get_value::<MyEditorState>(storage, PERSISTENCE_KEY).ok_or(envelope_error)
     }
 }
 
@@ -405,7 +365,12 @@ impl eframe::App for NodeGraphExample {
     /// If the persistence function is enabled,
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, PERSISTENCE_KEY, &self.state);
+        if let Some(save) = &self.unreadable_save {
+            storage.set_string(BACKUP_KEY, save.clone());
+        }
+        if let Ok(envelope) = self.state.save(&()) {
+            eframe::set_value(storage, PERSISTENCE_KEY, &envelope);
+        }
     }
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
@@ -413,6 +378,38 @@ impl eframe::App for NodeGraphExample {
         egui::TopBottomPanel::top("top").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 egui::widgets::global_dark_light_mode_switch(ui);
+                ui.checkbox(&mut self.state.read_only, "Read only");
+                ui.checkbox(&mut self.state.show_data_flow, "Show data flow");
+                let mut highlight = self.state.dependency_highlight != DependencyHighlight::Off;
+                if ui
+                    .checkbox(&mut highlight, "Highlight dependencies")
+                    .changed()
+                {
+                    self.state.dependency_highlight = if highlight {
+                        DependencyHighlight::Both
+                    } else {
+                        DependencyHighlight::Off
+                    };
+                }
+                ui.menu_button("Export", |ui| {
+                    if ui.button("Copy as DOT").clicked() {
+                        let dot = self.state.to_dot(true);
+                        ui.output_mut(|o| o.copied_text = dot);
+                        ui.close_menu();
+                    }
+                    if ui.button("Copy as Mermaid").clicked() {
+                        let mermaid = self.state.to_mermaid(false);
+                        ui.output_mut(|o| o.copied_text = mermaid);
+                        ui.close_menu();
+                    }
+                });
+                #[cfg(feature = "persistence")]
+                if let Some(err) = &self.load_error {
+                    ui.colored_label(ui.visuals().error_fg_color, err);
+                    if ui.small_button("Dismiss").clicked() {
+                        self.load_error = None;
+                    }
+                }
             });
         });
         let graph_response = egui::CentralPanel::default()
@@ -437,21 +434,40 @@ impl eframe::App for NodeGraphExample {
             }
         }
 
+        self.state.connection_annotations.clear();
         if let Some(node) = self.user_state.active_node {
             if self.state.graph.nodes.contains_key(node) {
-                let text = match evaluate_node(&self.state.graph, node, &mut HashMap::new()) {
-                    Ok(value) => format!("The result is: {:?}", value),
-                    Err(err) => format!("Execution error: {}", err),
-                };
-                ctx.debug_painter().text(
-                    egui::pos2(10.0, 35.0),
-                    egui::Align2::LEFT_TOP,
-                    text,
-                    TextStyle::Button.resolve(&ctx.style()),
-                    egui::Color32::WHITE,
-                );
+                let mut outputs_cache = HashMap::new();
+                let result = evaluate_node(&self.state.graph, node, &mut outputs_cache);
+                self.user_state.evaluation_error =
+                    (result.as_ref().err()).map(|err| (err.node_id, err.error.to_string()));
+                // Label the wires with the values flowing through them
+                if self.state.show_data_flow {
+                    let connections: Vec<_> = self.state.graph.iter_connections().collect();
+                    for (input, output) in connections {
+                        if let Some(value) = outputs_cache.get(&output) {
+                            let label = match value {
+                                MyValueType::Scalar { value } => format!("{:.2}", value),
+                                MyValueType::Vec2 { value } => {
+                                    format!("({:.2}, {:.2})", value.x, value.y)
+                                }
+                            };
+                            self.state.annotate_connection(input, output).label = Some(label);
+                        }
+                    }
+                }
+                if let Ok(value) = result {
+                    ctx.debug_painter().text(
+                        egui::pos2(10.0, 35.0),
+                        egui::Align2::LEFT_TOP,
+                        format!("The result is: {:?}", value),
+                        TextStyle::Button.resolve(&ctx.style()),
+                        egui::Color32::WHITE,
+                    );
+                }
             } else {
                 self.user_state.active_node = None;
+                self.user_state.evaluation_error = None;
             }
         }
     }
@@ -459,12 +475,27 @@ impl eframe::App for NodeGraphExample {
 
 type OutputsCache = HashMap<OutputId, MyValueType>;
 
+/// An error evaluating the graph, and the node that failed.
+#[derive(Debug)]
+pub struct EvaluationError {
+    pub node_id: NodeId,
+    pub error: anyhow::Error,
+}
+
+impl std::fmt::Display for EvaluationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for EvaluationError {}
+
 /// Recursively evaluates all dependencies of this node, then evaluates the node itself.
 pub fn evaluate_node(
     graph: &MyGraph,
     node_id: NodeId,
     outputs_cache: &mut OutputsCache,
-) -> anyhow::Result<MyValueType> {
+) -> Result<MyValueType, EvaluationError> {
     // To solve a similar problem as creating node types above, we define an
     // Evaluator as a convenience. It may be overkill for this small example,
     // but something like this makes the code much more readable when the
@@ -509,10 +540,10 @@ pub fn evaluate_node(
             populate_output(self.graph, self.outputs_cache, self.node_id, name, value)
         }
         fn input_vector(&mut self, name: &str) -> anyhow::Result<egui::Vec2> {
-            self.evaluate_input(name)?.try_to_vec2()
+            Ok(self.evaluate_input(name)?.try_to_vec2()?)
         }
         fn input_scalar(&mut self, name: &str) -> anyhow::Result<f32> {
-            self.evaluate_input(name)?.try_to_scalar()
+            Ok(self.evaluate_input(name)?.try_to_scalar()?)
         }
         fn output_vector(&mut self, name: &str, value: egui::Vec2) -> anyhow::Result<MyValueType> {
             self.populate_output(name, MyValueType::Vec2 { value })
@@ -524,42 +555,49 @@ pub fn evaluate_node(
 
     let node = &graph[node_id];
     let mut evaluator = Evaluator::new(graph, outputs_cache, node_id);
-    match node.user_data.template {
-        MyNodeTemplate::AddScalar => {
-            let a = evaluator.input_scalar("A")?;
-            let b = evaluator.input_scalar("B")?;
-            evaluator.output_scalar("out", a + b)
-        }
-        MyNodeTemplate::SubtractScalar => {
-            let a = evaluator.input_scalar("A")?;
-            let b = evaluator.input_scalar("B")?;
-            evaluator.output_scalar("out", a - b)
-        }
-        MyNodeTemplate::VectorTimesScalar => {
-            let scalar = evaluator.input_scalar("scalar")?;
-            let vector = evaluator.input_vector("vector")?;
-            evaluator.output_vector("out", vector * scalar)
-        }
-        MyNodeTemplate::AddVector => {
-            let v1 = evaluator.input_vector("v1")?;
-            let v2 = evaluator.input_vector("v2")?;
-            evaluator.output_vector("out", v1 + v2)
-        }
-        MyNodeTemplate::SubtractVector => {
-            let v1 = evaluator.input_vector("v1")?;
-            let v2 = evaluator.input_vector("v2")?;
-            evaluator.output_vector("out", v1 - v2)
-        }
-        MyNodeTemplate::MakeVector => {
-            let x = evaluator.input_scalar("x")?;
-            let y = evaluator.input_scalar("y")?;
-            evaluator.output_vector("out", egui::vec2(x, y))
-        }
-        MyNodeTemplate::MakeScalar => {
-            let value = evaluator.input_scalar("value")?;
-            evaluator.output_scalar("out", value)
+    let mut evaluate = || -> anyhow::Result<MyValueType> {
+        match node.user_data.template {
+            MyNodeTemplate::AddScalar => {
+                let a = evaluator.input_scalar("A")?;
+                let b = evaluator.input_scalar("B")?;
+                evaluator.output_scalar("out", a + b)
+            }
+            MyNodeTemplate::SubtractScalar => {
+                let a = evaluator.input_scalar("A")?;
+                let b = evaluator.input_scalar("B")?;
+                evaluator.output_scalar("out", a - b)
+            }
+            MyNodeTemplate::VectorTimesScalar => {
+                let scalar = evaluator.input_scalar("scalar")?;
+                let vector = evaluator.input_vector("vector")?;
+                evaluator.output_vector("out", vector * scalar)
+            }
+            MyNodeTemplate::AddVector => {
+                let v1 = evaluator.input_vector("v1")?;
+                let v2 = evaluator.input_vector("v2")?;
+                evaluator.output_vector("out", v1 + v2)
+            }
+            MyNodeTemplate::SubtractVector => {
+                let v1 = evaluator.input_vector("v1")?;
+                let v2 = evaluator.input_vector("v2")?;
+                evaluator.output_vector("out", v1 - v2)
+            }
+            MyNodeTemplate::MakeVector => {
+                let x = evaluator.input_scalar("x")?;
+                let y = evaluator.input_scalar("y")?;
+                evaluator.output_vector("out", egui::vec2(x, y))
+            }
+            MyNodeTemplate::MakeScalar => {
+                let value = evaluator.input_scalar("value")?;
+                evaluator.output_scalar("out", value)
+            }
         }
-    }
+    };
+    // The errors of the upstream nodes already know which node failed.
+    evaluate().map_err(|error| match error.downcast::<EvaluationError>() {
+        Ok(upstream_error) => upstream_error,
+        Err(error) => EvaluationError { node_id, error },
+    })
 }
 
 fn populate_output(